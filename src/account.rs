@@ -12,14 +12,21 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::collections::HashSet;
+
 use async_trait::async_trait;
 use clap::Subcommand;
-use golem_cloud_client::model::AccountData;
+use golem_cloud_client::model::{AccountData, Plan};
+use serde::Serialize;
+use uuid::Uuid;
 
 use crate::clients::account::AccountClient;
 use crate::clients::grant::GrantClient;
+use crate::clients::project::ProjectClient;
+use crate::clients::template::TemplateClient;
 use crate::clients::CloudAuthentication;
-use crate::model::{AccountId, GolemError, GolemResult, Role};
+use crate::model::{AccountId, GolemError, GolemResult, ProjectId, Role};
+use crate::table::TableColumns;
 
 #[derive(Subcommand, Debug)]
 #[command()]
@@ -57,6 +64,10 @@ pub enum AccountSubcommand {
     #[command()]
     Delete {},
 
+    /// Show the limits of the account's plan, with the current usage where known
+    #[command()]
+    Plan {},
+
     /// Manage the account's roles
     #[command()]
     Grant {
@@ -85,6 +96,201 @@ pub enum GrantSubcommand {
         #[arg(value_name = "ROLE")]
         role: Role,
     },
+
+    /// Check whether the account has a role
+    #[command()]
+    Check {
+        #[arg(value_name = "ROLE")]
+        role: Role,
+    },
+
+    /// Add and remove roles so the account has exactly the given ones
+    #[command()]
+    Sync {
+        /// At least one role, use `grant delete` to remove the last ones
+        #[arg(value_name = "ROLES", required = true, num_args = 1..)]
+        roles: Vec<Role>,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanView {
+    pub plan_id: Uuid,
+    pub limits: Vec<LimitView>,
+}
+
+impl TableColumns for PlanView {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitView {
+    pub resource: String,
+    pub limit: i64,
+    /// Missing when the API doesn't expose the current usage
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub used: Option<i64>,
+}
+
+impl LimitView {
+    fn new(resource: &str, limit: i64, used: Option<i64>) -> LimitView {
+        LimitView {
+            resource: resource.to_string(),
+            limit,
+            used,
+        }
+    }
+}
+
+/// Usage of an account that can be counted through the API.
+///
+/// Workers, storage, gas and uploads are not listed by the API, so only projects and
+/// templates are counted.
+pub struct Usage {
+    pub projects: i64,
+    pub templates: i64,
+}
+
+impl Usage {
+    pub async fn count<P: ProjectClient + Sync + Send, T: TemplateClient + Sync + Send>(
+        account_id: &AccountId,
+        projects: &P,
+        templates: &T,
+    ) -> Result<Usage, GolemError> {
+        let owned: Vec<ProjectId> = projects
+            .find(None)
+            .await?
+            .into_iter()
+            .filter(|p| p.project_data.owner_account_id == account_id.id)
+            .map(|p| ProjectId(p.project_id))
+            .collect();
+
+        let mut template_ids = HashSet::new();
+        for project_id in &owned {
+            for template in templates.find(Some(project_id.clone()), None).await? {
+                template_ids.insert(template.template_id);
+            }
+        }
+
+        Ok(Usage {
+            projects: owned.len() as i64,
+            templates: template_ids.len() as i64,
+        })
+    }
+}
+
+impl PlanView {
+    pub fn new(plan: Plan, usage: &Usage) -> PlanView {
+        let data = plan.plan_data;
+
+        PlanView {
+            plan_id: plan.plan_id,
+            limits: vec![
+                LimitView::new("projects", data.project_limit.into(), Some(usage.projects)),
+                LimitView::new(
+                    "templates",
+                    data.template_limit.into(),
+                    Some(usage.templates),
+                ),
+                LimitView::new("workers", data.worker_limit.into(), None),
+                LimitView::new("storage", data.storage_limit.into(), None),
+                LimitView::new("monthlyGas", data.monthly_gas_limit, None),
+                LimitView::new("monthlyUpload", data.monthly_upload_limit.into(), None),
+            ],
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleCheckView {
+    pub role: Role,
+    pub granted: bool,
+}
+
+impl TableColumns for RoleCheckView {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RoleSyncView {
+    pub added: Vec<Role>,
+    pub removed: Vec<Role>,
+}
+
+impl TableColumns for RoleSyncView {}
+
+async fn check_role<G: GrantClient + Sync + Send>(
+    grant: &G,
+    account_id: AccountId,
+    role: Role,
+) -> Result<RoleCheckView, GolemError> {
+    let granted = grant.get(account_id, role).await?.is_some();
+
+    Ok(RoleCheckView { role, granted })
+}
+
+async fn sync_roles<G: GrantClient + Sync + Send>(
+    grant: &G,
+    account_id: AccountId,
+    roles: Vec<Role>,
+) -> Result<RoleSyncView, GolemError> {
+    // Syncing to nothing would revoke every role, Admin included
+    if roles.is_empty() {
+        return Err(GolemError::new(
+            "At least one role is required to sync to".to_string(),
+        ));
+    }
+
+    let current = grant.get_all(account_id.clone()).await?;
+    let mut result = RoleSyncView::default();
+
+    for role in roles.iter().filter(|r| !current.contains(r)) {
+        if !result.added.contains(role) {
+            grant.put(account_id.clone(), *role).await?;
+            result.added.push(*role);
+        }
+    }
+
+    for role in current.iter().filter(|r| !roles.contains(r)) {
+        grant.delete(account_id.clone(), *role).await?;
+        result.removed.push(*role);
+    }
+
+    Ok(result)
+}
+
+async fn handle_grant<G: GrantClient + Sync + Send>(
+    grant: &G,
+    account_id: AccountId,
+    subcommand: GrantSubcommand,
+) -> Result<GolemResult, GolemError> {
+    match subcommand {
+        GrantSubcommand::Get {} => {
+            let roles = grant.get_all(account_id).await?;
+
+            Ok(GolemResult::Ok(Box::new(roles)))
+        }
+        GrantSubcommand::Add { role } => {
+            grant.put(account_id, role).await?;
+
+            Ok(GolemResult::Ok(Box::new("RoleGranted".to_string())))
+        }
+        GrantSubcommand::Delete { role } => {
+            grant.delete(account_id, role).await?;
+
+            Ok(GolemResult::Ok(Box::new("RoleRemoved".to_string())))
+        }
+        GrantSubcommand::Check { role } => {
+            let check = check_role(grant, account_id, role).await?;
+
+            Ok(GolemResult::Ok(Box::new(check)))
+        }
+        GrantSubcommand::Sync { roles } => {
+            let sync = sync_roles(grant, account_id, roles).await?;
+
+            Ok(GolemResult::Ok(Box::new(sync)))
+        }
+    }
 }
 
 #[async_trait]
@@ -97,14 +303,27 @@ pub trait AccountHandler {
     ) -> Result<GolemResult, GolemError>;
 }
 
-pub struct AccountHandlerLive<C: AccountClient + Sync + Send, G: GrantClient + Sync + Send> {
+pub struct AccountHandlerLive<
+    'p,
+    C: AccountClient + Sync + Send,
+    G: GrantClient + Sync + Send,
+    P: ProjectClient + Sync + Send,
+    T: TemplateClient + Sync + Send,
+> {
     pub client: C,
     pub grant: G,
+    pub projects: &'p P,
+    pub templates: T,
 }
 
 #[async_trait]
-impl<C: AccountClient + Sync + Send, G: GrantClient + Sync + Send> AccountHandler
-    for AccountHandlerLive<C, G>
+impl<
+        'p,
+        C: AccountClient + Sync + Send,
+        G: GrantClient + Sync + Send,
+        P: ProjectClient + Sync + Send,
+        T: TemplateClient + Sync + Send,
+    > AccountHandler for AccountHandlerLive<'p, C, G, P, T>
 {
     async fn handle(
         &self,
@@ -147,23 +366,140 @@ impl<C: AccountClient + Sync + Send, G: GrantClient + Sync + Send> AccountHandle
                 self.client.delete(&account_id).await?;
                 Ok(GolemResult::Str("Deleted".to_string()))
             }
-            AccountSubcommand::Grant { subcommand } => match subcommand {
-                GrantSubcommand::Get {} => {
-                    let roles = self.grant.get_all(account_id).await?;
+            AccountSubcommand::Plan {} => {
+                let plan = self.client.get_plan(&account_id).await?;
+                let usage = Usage::count(&account_id, self.projects, &self.templates).await?;
+
+                Ok(GolemResult::Ok(Box::new(PlanView::new(plan, &usage))))
+            }
+            AccountSubcommand::Grant { subcommand } => {
+                handle_grant(&self.grant, account_id, subcommand).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+
+    use crate::account::{check_role, handle_grant, sync_roles, GrantSubcommand};
+    use crate::clients::grant::GrantClient;
+    use crate::model::{AccountId, GolemError, Role};
+
+    struct FakeGrantClient {
+        roles: Mutex<Vec<Role>>,
+    }
+
+    impl FakeGrantClient {
+        fn new(roles: Vec<Role>) -> FakeGrantClient {
+            FakeGrantClient {
+                roles: Mutex::new(roles),
+            }
+        }
+
+        fn roles(&self) -> Vec<Role> {
+            self.roles.lock().unwrap().clone()
+        }
+    }
 
-                    Ok(GolemResult::Ok(Box::new(roles)))
-                }
-                GrantSubcommand::Add { role } => {
-                    self.grant.put(account_id, role).await?;
+    #[async_trait]
+    impl GrantClient for FakeGrantClient {
+        async fn get_all(&self, _account_id: AccountId) -> Result<Vec<Role>, GolemError> {
+            Ok(self.roles())
+        }
 
-                    Ok(GolemResult::Ok(Box::new("RoleGranted".to_string())))
-                }
-                GrantSubcommand::Delete { role } => {
-                    self.grant.put(account_id, role).await?;
+        async fn get(
+            &self,
+            _account_id: AccountId,
+            role: Role,
+        ) -> Result<Option<Role>, GolemError> {
+            Ok(self.roles().into_iter().find(|r| *r == role))
+        }
 
-                    Ok(GolemResult::Ok(Box::new("RoleRemoved".to_string())))
-                }
-            },
+        async fn put(&self, _account_id: AccountId, role: Role) -> Result<(), GolemError> {
+            let mut roles = self.roles.lock().unwrap();
+            if !roles.contains(&role) {
+                roles.push(role);
+            }
+            Ok(())
         }
+
+        async fn delete(&self, _account_id: AccountId, role: Role) -> Result<(), GolemError> {
+            self.roles.lock().unwrap().retain(|r| *r != role);
+            Ok(())
+        }
+    }
+
+    fn account() -> AccountId {
+        AccountId::new("account".to_string())
+    }
+
+    #[tokio::test]
+    async fn delete_removes_the_role() {
+        let grant = FakeGrantClient::new(vec![Role::Admin, Role::ViewProject]);
+
+        handle_grant(
+            &grant,
+            account(),
+            GrantSubcommand::Delete { role: Role::Admin },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(grant.roles(), vec![Role::ViewProject]);
+    }
+
+    #[tokio::test]
+    async fn check_reports_whether_the_role_is_granted() {
+        let grant = FakeGrantClient::new(vec![Role::ViewProject]);
+
+        let granted = check_role(&grant, account(), Role::ViewProject)
+            .await
+            .unwrap();
+        let missing = check_role(&grant, account(), Role::Admin).await.unwrap();
+
+        assert!(granted.granted);
+        assert!(!missing.granted);
+    }
+
+    #[tokio::test]
+    async fn sync_adds_and_removes_roles() {
+        let grant = FakeGrantClient::new(vec![Role::Admin, Role::ViewProject]);
+
+        let result = sync_roles(
+            &grant,
+            account(),
+            vec![Role::ViewProject, Role::CreateProject, Role::CreateProject],
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(result.added, vec![Role::CreateProject]);
+        assert_eq!(result.removed, vec![Role::Admin]);
+        assert_eq!(grant.roles(), vec![Role::ViewProject, Role::CreateProject]);
+    }
+
+    #[tokio::test]
+    async fn sync_to_current_roles_changes_nothing() {
+        let grant = FakeGrantClient::new(vec![Role::Admin]);
+
+        let result = sync_roles(&grant, account(), vec![Role::Admin])
+            .await
+            .unwrap();
+
+        assert!(result.added.is_empty());
+        assert!(result.removed.is_empty());
+        assert_eq!(grant.roles(), vec![Role::Admin]);
+    }
+
+    #[tokio::test]
+    async fn sync_to_no_roles_is_rejected() {
+        let grant = FakeGrantClient::new(vec![Role::Admin]);
+
+        assert!(sync_roles(&grant, account(), vec![]).await.is_err());
+        assert_eq!(grant.roles(), vec![Role::Admin]);
     }
 }
@@ -12,21 +12,54 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{BufReader, BufWriter};
+pub mod store;
+
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
+use clap::Subcommand;
 use golem_cloud_client::model::{OAuth2Data, Token, TokenSecret, UnsafeToken};
-use indoc::printdoc;
+use golem_cloud_client::{Context, Security};
+use indoc::eprintdoc;
 use serde::{Deserialize, Serialize};
-use tracing::info;
 use uuid::Uuid;
 
+use crate::auth::store::CredentialStore;
 use crate::clients::login::LoginClient;
+use crate::clients::token::{TokenClient, TokenClientLive};
 use crate::clients::CloudAuthentication;
-use crate::model::GolemError;
+use crate::config::Config;
+use crate::model::{Format, GolemError, GolemResult, TokenId};
+use crate::table::TableColumns;
+
+#[derive(Subcommand, Debug)]
+#[command()]
+pub enum AuthSubcommand {
+    /// Logs in with OAuth2, replacing the stored token
+    #[command()]
+    Login {
+        /// Where to keep the token from now on: keyring, encrypted or file
+        #[arg(long)]
+        store: Option<CredentialStore>,
+    },
+
+    /// Deletes the stored token
+    #[command()]
+    Logout {
+        /// Also revoke the token, so it can't be used anymore
+        #[arg(long)]
+        revoke: bool,
+    },
+
+    /// Shows the account and token in use
+    #[command()]
+    Status {},
+}
+
+/// Tokens expiring sooner than this are warned about.
+const EXPIRY_WARNING_HOURS: i64 = 24;
 
 #[async_trait]
 pub trait Auth {
@@ -35,11 +68,30 @@ pub trait Auth {
         manual_token: Option<Uuid>,
         config_dir: PathBuf,
     ) -> Result<CloudAuthentication, GolemError>;
+
+    /// Replaces a stored token that can't be used anymore, logging in again when interactive.
+    async fn reauthenticate(
+        &self,
+        config_dir: PathBuf,
+        reason: &str,
+    ) -> Result<CloudAuthentication, GolemError>;
 }
 
 #[derive(Clone, PartialEq, Eq)]
 pub struct AuthLive<L: LoginClient + Send + Sync> {
     pub login: L,
+    pub store: CredentialStore,
+    pub prompt: LoginPrompt,
+}
+
+/// How the OAuth2 device flow is presented, and how long it is waited for.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct LoginPrompt {
+    /// With json, the prompt is a single JSON event on stderr
+    pub format: Format,
+    pub open_browser: bool,
+    /// Waits until the device code expires when missing
+    pub timeout: Option<std::time::Duration>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -59,75 +111,143 @@ struct CloudAuthenticationConfigData {
 }
 
 impl<L: LoginClient + Send + Sync> AuthLive<L> {
-    fn read_from_file(&self, config_dir: &Path) -> Option<CloudAuthentication> {
-        let file = File::open(self.config_path(config_dir)).ok()?; // TODO log
+    /// Reads the stored token, along with the store it was found in.
+    ///
+    /// Tokens stored in the plaintext file by earlier versions are still picked up when the
+    /// configured store is empty.
+    fn read_stored(
+        &self,
+        config_dir: &Path,
+    ) -> Result<Option<(CloudAuthentication, CredentialStore)>, GolemError> {
+        let store = self.store;
+        let found = match store.read(config_dir) {
+            Ok(Some(data)) => Some((data, store)),
+            // A token the user already keeps in the plaintext file is used even when the
+            // configured store can't be read
+            result if store != CredentialStore::File => {
+                match CredentialStore::File.read(config_dir)? {
+                    Some(data) => Some((data, CredentialStore::File)),
+                    None => result?.map(|data| (data, store)),
+                }
+            }
+            result => result?.map(|data| (data, store)),
+        };
 
-        let reader = BufReader::new(file);
+        let Some((data, store)) = found else {
+            return Ok(None);
+        };
 
-        let parsed: serde_json::Result<CloudAuthenticationConfig> = serde_json::from_reader(reader);
+        let parsed: serde_json::Result<CloudAuthenticationConfig> = serde_json::from_slice(&data);
 
         match parsed {
-            Ok(conf) => Some(CloudAuthentication(UnsafeToken {
-                data: Token {
-                    id: conf.data.id,
-                    account_id: conf.data.account_id,
-                    created_at: conf.data.created_at,
-                    expires_at: conf.data.expires_at,
-                },
-                secret: TokenSecret { value: conf.secret },
-            })),
-            Err(err) => {
-                info!("Parsing failed: {err}"); // TODO configure
-                None
-            }
+            Ok(conf) => Ok(Some((
+                CloudAuthentication(UnsafeToken {
+                    data: Token {
+                        id: conf.data.id,
+                        account_id: conf.data.account_id,
+                        created_at: conf.data.created_at,
+                        expires_at: conf.data.expires_at,
+                    },
+                    secret: TokenSecret { value: conf.secret },
+                }),
+                store,
+            ))),
+            Err(err) => Err(GolemError::new(format!(
+                "Failed to parse the token stored in {}, run `auth login` to replace it: {err}",
+                store.location(config_dir)
+            ))),
         }
     }
 
-    fn config_path(&self, config_dir: &Path) -> PathBuf {
-        config_dir.join("cloud_authentication.json")
+    /// Removes the stored token from the configured store and the plaintext file.
+    fn delete_stored(&self, config_dir: &Path) -> Result<bool, GolemError> {
+        let store = self.store;
+        let deleted = store.delete(config_dir)?;
+        let deleted_file =
+            store != CredentialStore::File && CredentialStore::File.delete(config_dir)?;
+
+        Ok(deleted || deleted_file)
     }
 
-    fn store_file(&self, token: &UnsafeToken, config_dir: &Path) {
-        match create_dir_all(config_dir) {
-            Ok(_) => {}
-            Err(err) => {
-                info!("Can't create config directory: {err}");
+    async fn device_flow(&self) -> Result<UnsafeToken, GolemError> {
+        let data = self.login.start_oauth2().await?;
+        inform_user(&data, self.prompt.format);
+
+        if self.prompt.open_browser {
+            if let Err(err) = webbrowser::open(&data.url) {
+                eprintln!("Warning: failed to open a browser: {err}");
             }
         }
-        let file_res = OpenOptions::new()
-            .read(true)
-            .write(true)
-            .create(true)
-            .open(self.config_path(config_dir));
-        let file = match file_res {
-            Ok(file) => file,
-            Err(err) => {
-                info!("Can't open file: {err}");
-                return;
-            }
-        };
-        let writer = BufWriter::new(file);
-        let data = CloudAuthenticationConfig {
-            data: CloudAuthenticationConfigData {
-                id: token.data.id,
-                account_id: token.data.account_id.clone(),
-                created_at: token.data.created_at,
-                expires_at: token.data.expires_at,
-            },
-            secret: token.secret.value,
-        };
-        let res = serde_json::to_writer_pretty(writer, &data);
 
-        if let Err(err) = res {
-            info!("File sawing error: {err}");
+        self.wait_for_login(&data).await
+    }
+
+    /// Waits for the device flow to be completed, at most until the code expires or the
+    /// configured timeout passes, showing a countdown on terminals.
+    async fn wait_for_login(&self, data: &OAuth2Data) -> Result<UnsafeToken, GolemError> {
+        let code_left = (data.expires - Utc::now()).to_std().unwrap_or_default();
+        let timeout = self
+            .prompt
+            .timeout
+            .map_or(code_left, |timeout| timeout.min(code_left));
+        let deadline = tokio::time::Instant::now() + timeout;
+
+        let countdown = self.prompt.format != Format::Json && std::io::stderr().is_terminal();
+        if !countdown && self.prompt.format != Format::Json {
+            eprintln!("Waiting...");
+        }
+
+        let complete = self.login.complete_oauth2(data.encoded_session.clone());
+        tokio::pin!(complete);
+        let mut ticks = tokio::time::interval(std::time::Duration::from_secs(1));
+
+        loop {
+            tokio::select! {
+                result = &mut complete => {
+                    if countdown {
+                        eprintln!();
+                    }
+                    return result;
+                }
+                _ = ticks.tick() => {
+                    let left = deadline.saturating_duration_since(tokio::time::Instant::now());
+                    if left.is_zero() {
+                        if countdown {
+                            eprintln!();
+                        }
+                        return Err(GolemError::new(format!(
+                            "Timed out after {} seconds waiting for the OAuth2 login",
+                            timeout.as_secs()
+                        )));
+                    }
+                    if countdown {
+                        let secs = left.as_secs();
+                        eprint!("\rWaiting for the login, {}:{:02} left ", secs / 60, secs % 60);
+                        let _ = std::io::stderr().flush();
+                    }
+                }
+            }
         }
     }
 
+    /// Logs in, failing when the token can't be stored.
+    async fn login(
+        &self,
+        store: CredentialStore,
+        config_dir: &Path,
+    ) -> Result<CloudAuthentication, GolemError> {
+        let token = self.device_flow().await?;
+        save_token(store, &token, config_dir)?;
+        Ok(CloudAuthentication(token))
+    }
+
     async fn oauth2(&self, config_dir: &Path) -> Result<CloudAuthentication, GolemError> {
-        let data = self.login.start_oauth2().await?;
-        inform_user(&data);
-        let token = self.login.complete_oauth2(data.encoded_session).await?;
-        self.store_file(&token, config_dir);
+        let token = self.device_flow().await?;
+        if let Err(GolemError { message: err, .. }) = save_token(self.store, &token, config_dir) {
+            eprintln!(
+                "Warning: the token could not be stored, choose another store with `auth login --store`: {err}"
+            );
+        }
         Ok(CloudAuthentication(token))
     }
 
@@ -135,24 +255,95 @@ impl<L: LoginClient + Send + Sync> AuthLive<L> {
         &self,
         config_dir: PathBuf,
     ) -> Result<CloudAuthentication, GolemError> {
-        if let Some(data) = self.read_from_file(&config_dir) {
-            Ok(data)
-        } else {
-            self.oauth2(&config_dir).await
+        match self.read_stored(&config_dir)? {
+            Some((data, _)) if data.0.data.expires_at <= Utc::now() => {
+                let reason = format!(
+                    "The stored token expired at {}",
+                    data.0.data.expires_at.format("%F %T %Z")
+                );
+                self.reauthenticate(config_dir, &reason).await
+            }
+            Some((data, _)) => {
+                warn_if_expiring(&data.0.data);
+                Ok(data)
+            }
+            // Waiting on a device flow nobody can answer would hang CI jobs
+            None if !is_interactive() => Err(GolemError::not_authenticated(
+                "no token was given or stored, and logging in needs a terminal",
+            )),
+            None => self.oauth2(&config_dir).await,
         }
     }
 }
 
-fn inform_user(data: &OAuth2Data) {
+/// Keeps a token in a store, removing any plaintext copy when the store is another one.
+pub fn save_token(
+    store: CredentialStore,
+    token: &UnsafeToken,
+    config_dir: &Path,
+) -> Result<(), GolemError> {
+    let data = CloudAuthenticationConfig {
+        data: CloudAuthenticationConfigData {
+            id: token.data.id,
+            account_id: token.data.account_id.clone(),
+            created_at: token.data.created_at,
+            expires_at: token.data.expires_at,
+        },
+        secret: token.secret.value,
+    };
+    let data = serde_json::to_vec_pretty(&data)
+        .map_err(|e| GolemError::new(format!("Failed to serialize the token: {e}")))?;
+
+    store.write(config_dir, &data)?;
+
+    // Don't leave a plaintext copy next to a token kept elsewhere
+    if store != CredentialStore::File {
+        CredentialStore::File.delete(config_dir)?;
+    }
+
+    Ok(())
+}
+
+/// Whether the user can answer the OAuth2 device flow.
+pub fn is_interactive() -> bool {
+    std::io::stdin().is_terminal() && std::io::stderr().is_terminal()
+}
+
+fn warn_if_expiring(token: &Token) {
+    let left = token.expires_at.signed_duration_since(Utc::now());
+
+    if left < Duration::hours(EXPIRY_WARNING_HOURS) {
+        eprintln!(
+            "Warning: the token expires in {} minutes, at {}",
+            left.num_minutes(),
+            token.expires_at.format("%F %T %Z")
+        );
+    }
+}
+
+fn inform_user(data: &OAuth2Data, format: Format) {
+    let expires: DateTime<Utc> = data.expires;
+    let url = &data.url;
+    let user_code = &data.user_code;
+
+    // Prompts go to stderr, so they don't end up in the command's output
+    if format == Format::Json {
+        let event = serde_json::json!({
+            "event": "oauth2DeviceCode",
+            "url": url,
+            "userCode": user_code,
+            "expiresAt": expires,
+        });
+        eprintln!("{event}");
+        return;
+    }
+
     let box_url_line = String::from_utf8(vec![b'-'; data.url.len() + 2]).unwrap();
     let box_code_line = String::from_utf8(vec![b'-'; data.user_code.len() + 2]).unwrap();
-    let expires: DateTime<Utc> = data.expires;
     let expires_in = expires.signed_duration_since(Utc::now()).num_minutes();
     let expires_at = expires.format("%T");
-    let url = &data.url;
-    let user_code = &data.user_code;
 
-    printdoc! {"
+    eprintdoc! {"
         >>
         >>  Application requests to perform OAuth2
         >>  authorization.
@@ -171,7 +362,6 @@ fn inform_user(data: &OAuth2Data) {
         >>
         >>  Code will expire in {expires_in} minutes at {expires_at}.
         >>
-        Waiting...
     "};
 }
 
@@ -188,9 +378,166 @@ impl<L: LoginClient + Send + Sync> Auth for AuthLive<L> {
             };
             let data = self.login.token_details(secret.clone()).await?;
 
+            if data.expires_at <= Utc::now() {
+                return Err(GolemError::new(format!(
+                    "The given auth token expired at {}",
+                    data.expires_at.format("%F %T %Z")
+                )));
+            }
+            warn_if_expiring(&data);
+
             Ok(CloudAuthentication(UnsafeToken { data, secret }))
         } else {
             self.config_authentication(config_dir).await
         }
     }
+
+    async fn reauthenticate(
+        &self,
+        config_dir: PathBuf,
+        reason: &str,
+    ) -> Result<CloudAuthentication, GolemError> {
+        if is_interactive() {
+            eprintln!("{reason}, logging in again");
+            self.oauth2(&config_dir).await
+        } else {
+            Err(GolemError::not_authenticated(&format!(
+                "{reason}, run `auth login` to log in again"
+            )))
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuthStatusView {
+    pub account_id: String,
+    pub token_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub expired: bool,
+    pub source: String,
+}
+
+impl TableColumns for AuthStatusView {}
+
+impl AuthStatusView {
+    fn new(token: &Token, source: String) -> AuthStatusView {
+        AuthStatusView {
+            account_id: token.account_id.clone(),
+            token_id: token.id,
+            created_at: token.created_at,
+            expires_at: token.expires_at,
+            expired: token.expires_at <= Utc::now(),
+            source,
+        }
+    }
+}
+
+/// Handles the auth commands, which run before the implicit authentication of other commands.
+pub struct AuthHandlerLive<'a, L: LoginClient + Send + Sync> {
+    pub auth: &'a AuthLive<L>,
+    /// Context without authentication, used to revoke the stored token
+    pub context: Context,
+    pub config_dir: PathBuf,
+    /// Directory of the stored authentication, which depends on the profile
+    pub auth_dir: PathBuf,
+    pub manual_token: Option<Uuid>,
+}
+
+impl<'a, L: LoginClient + Send + Sync> AuthHandlerLive<'a, L> {
+    pub async fn handle(&self, subcommand: AuthSubcommand) -> Result<GolemResult, GolemError> {
+        match subcommand {
+            AuthSubcommand::Login { store } => {
+                let previous = self.auth.store;
+                let chosen = store.filter(|store| *store != previous);
+                let store = chosen.unwrap_or(previous);
+                store.check_available(&self.auth_dir)?;
+
+                let CloudAuthentication(token) = self.auth.login(store, &self.auth_dir).await?;
+
+                // Only a successful login switches stores, so a failed one keeps the old token
+                if let Some(store) = chosen {
+                    if previous != store {
+                        previous.delete(&self.auth_dir)?;
+                    }
+
+                    let mut config = Config::read(&self.config_dir)?;
+                    config.credential_store = Some(store);
+                    config.store(&self.config_dir)?;
+                }
+
+                Ok(GolemResult::Ok(Box::new(AuthStatusView::new(
+                    &token.data,
+                    format!("stored in {}", store.location(&self.auth_dir)),
+                ))))
+            }
+            AuthSubcommand::Logout { revoke } => {
+                let stored = match self.auth.read_stored(&self.auth_dir) {
+                    Ok(stored) => stored,
+                    // An unreadable token can't be revoked, but it can still be removed
+                    Err(err) if !revoke => {
+                        eprintln!("Warning: {err}");
+                        self.auth.delete_stored(&self.auth_dir)?;
+                        return Ok(GolemResult::Str("Removed the stored token".to_string()));
+                    }
+                    Err(err) => return Err(err),
+                };
+                let Some((CloudAuthentication(token), _)) = stored else {
+                    return Ok(GolemResult::Str("Not logged in".to_string()));
+                };
+
+                if revoke {
+                    let tokens = TokenClientLive {
+                        client: golem_cloud_client::api::TokenClientLive {
+                            context: Context {
+                                security_token: Security::Bearer(token.secret.value.to_string()),
+                                ..self.context.clone()
+                            },
+                        },
+                    };
+                    let account_id = CloudAuthentication(token.clone()).account_id();
+
+                    tokens.delete(&account_id, TokenId(token.data.id)).await?;
+                }
+
+                self.auth.delete_stored(&self.auth_dir)?;
+
+                Ok(GolemResult::Str(if revoke {
+                    format!(
+                        "Logged out of account {}, token revoked",
+                        token.data.account_id
+                    )
+                } else {
+                    format!("Logged out of account {}", token.data.account_id)
+                }))
+            }
+            AuthSubcommand::Status {} => {
+                if let Some(manual_token) = self.manual_token {
+                    let token = self
+                        .auth
+                        .login
+                        .token_details(TokenSecret {
+                            value: manual_token,
+                        })
+                        .await?;
+
+                    return Ok(GolemResult::Ok(Box::new(AuthStatusView::new(
+                        &token,
+                        "given auth token".to_string(),
+                    ))));
+                }
+
+                match self.auth.read_stored(&self.auth_dir)? {
+                    Some((CloudAuthentication(token), store)) => {
+                        Ok(GolemResult::Ok(Box::new(AuthStatusView::new(
+                            &token.data,
+                            format!("stored in {}", store.location(&self.auth_dir)),
+                        ))))
+                    }
+                    None => Ok(GolemResult::Str("Not logged in".to_string())),
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,300 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter};
+use std::fs::{create_dir_all, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use tracing::info;
+
+use crate::model::GolemError;
+
+const KEYRING_SERVICE: &str = "golem-cloud-cli";
+const ENCRYPTED_MAGIC: &[u8] = b"GOLEMENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+/// Where the token of the OAuth2 login is kept.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Default, EnumIter, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CredentialStore {
+    /// The OS keyring, the Secret Service on Linux
+    #[default]
+    Keyring,
+    /// A file encrypted with a passphrase, taken from `GOLEM_CREDENTIAL_PASSPHRASE` or prompted
+    Encrypted,
+    /// A plaintext file only readable by the user
+    File,
+}
+
+impl Display for CredentialStore {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Keyring => "keyring",
+            Self::Encrypted => "encrypted",
+            Self::File => "file",
+        };
+        Display::fmt(&s, f)
+    }
+}
+
+impl FromStr for CredentialStore {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keyring" => Ok(CredentialStore::Keyring),
+            "encrypted" => Ok(CredentialStore::Encrypted),
+            "file" => Ok(CredentialStore::File),
+            _ => {
+                let all = CredentialStore::iter()
+                    .map(|x| format!("\"{x}\""))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Err(format!(
+                    "Unknown credential store: {s}. Expected one of {all}"
+                ))
+            }
+        }
+    }
+}
+
+fn plaintext_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("cloud_authentication.json")
+}
+
+fn encrypted_path(config_dir: &Path) -> PathBuf {
+    config_dir.join("cloud_authentication.enc")
+}
+
+/// Keyring entries are keyed by the config directory, so every profile has its own.
+fn keyring_entry(config_dir: &Path) -> Result<keyring::Entry, GolemError> {
+    keyring::Entry::new(KEYRING_SERVICE, &keyring_key(config_dir).to_string_lossy())
+        .map_err(|e| GolemError::new(format!("Failed to access the keyring: {e}")))
+}
+
+/// The canonical config directory, so relative paths and symlinks name the same entry. The
+/// directory may not exist yet, so its nearest existing ancestor is canonicalized instead.
+fn keyring_key(config_dir: &Path) -> PathBuf {
+    let absolute = std::path::absolute(config_dir).unwrap_or_else(|_| config_dir.to_path_buf());
+
+    let mut missing = Vec::new();
+    let mut existing = absolute.as_path();
+    loop {
+        if let Ok(canonical) = existing.canonicalize() {
+            return missing
+                .iter()
+                .rev()
+                .fold(canonical, |path, name| path.join(name));
+        }
+        match (existing.parent(), existing.file_name()) {
+            (Some(parent), Some(name)) => {
+                missing.push(name);
+                existing = parent;
+            }
+            _ => return absolute,
+        }
+    }
+}
+
+fn read_file(path: &Path) -> Result<Option<Vec<u8>>, GolemError> {
+    match std::fs::read(path) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(GolemError::new(format!("Failed to read {path:?}: {err}"))),
+    }
+}
+
+/// Writes a file readable only by the user, replacing any previous content.
+pub(crate) fn write_private_file(path: &Path, bytes: &[u8]) -> Result<(), GolemError> {
+    let write_err = |e: std::io::Error| GolemError::new(format!("Failed to write {path:?}: {e}"));
+
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir).map_err(write_err)?;
+    }
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path).map_err(write_err)?;
+
+    // The mode only applies to new files, so older files are restricted as well
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(std::fs::Permissions::from_mode(0o600))
+            .map_err(write_err)?;
+    }
+
+    file.write_all(bytes).map_err(write_err)
+}
+
+fn remove_file(path: &Path) -> Result<bool, GolemError> {
+    match std::fs::remove_file(path) {
+        Ok(()) => Ok(true),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(false),
+        Err(err) => Err(GolemError::new(format!("Failed to remove {path:?}: {err}"))),
+    }
+}
+
+fn passphrase(confirm: bool) -> Result<String, GolemError> {
+    if let Ok(passphrase) = std::env::var("GOLEM_CREDENTIAL_PASSPHRASE") {
+        return Ok(passphrase);
+    }
+
+    let read = |prompt: &str| {
+        rpassword::prompt_password(prompt)
+            .map_err(|e| GolemError::new(format!("Failed to read the passphrase: {e}")))
+    };
+
+    let passphrase = read("Credential passphrase: ")?;
+    if confirm && read("Repeat the passphrase: ")? != passphrase {
+        return Err(GolemError::new("The passphrases don't match".to_string()));
+    }
+
+    Ok(passphrase)
+}
+
+fn cipher(passphrase: &str, salt: &[u8]) -> Result<ChaCha20Poly1305, GolemError> {
+    let mut key = [0u8; 32];
+    Argon2::default()
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| GolemError::new(format!("Failed to derive the credential key: {e}")))?;
+
+    Ok(ChaCha20Poly1305::new(Key::from_slice(&key)))
+}
+
+fn encrypt(data: &[u8]) -> Result<Vec<u8>, GolemError> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let encrypted = cipher(&passphrase(true)?, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|_| GolemError::new("Failed to encrypt the credentials".to_string()))?;
+
+    Ok([ENCRYPTED_MAGIC, &salt, &nonce, &encrypted].concat())
+}
+
+fn decrypt(bytes: &[u8], path: &Path) -> Result<Vec<u8>, GolemError> {
+    let header_len = ENCRYPTED_MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if bytes.len() < header_len || !bytes.starts_with(ENCRYPTED_MAGIC) {
+        return Err(GolemError::new(format!(
+            "{path:?} is not an encrypted credential file"
+        )));
+    }
+
+    let (salt, rest) = bytes[ENCRYPTED_MAGIC.len()..].split_at(SALT_LEN);
+    let (nonce, encrypted) = rest.split_at(NONCE_LEN);
+
+    cipher(&passphrase(false)?, salt)?
+        .decrypt(Nonce::from_slice(nonce), encrypted)
+        .map_err(|_| GolemError::new(format!("Wrong passphrase for {path:?}")))
+}
+
+impl CredentialStore {
+    /// Fails when the keyring can't be used, as on machines without a Secret Service, so the
+    /// token is only kept in a file once the user chose so.
+    pub fn check_available(self, config_dir: &Path) -> Result<(), GolemError> {
+        if self != CredentialStore::Keyring {
+            return Ok(());
+        }
+
+        match keyring_entry(config_dir)?.get_password() {
+            Ok(_) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(GolemError::new(format!(
+                "The keyring is not available: {err}. Choose another store with `auth login --store encrypted`, or `auth login --store file` to keep the token in plaintext"
+            ))),
+        }
+    }
+
+    /// Human readable place of the stored credentials.
+    pub fn location(&self, config_dir: &Path) -> String {
+        match self {
+            CredentialStore::Keyring => format!(
+                "the {KEYRING_SERVICE} keyring entry for {}",
+                config_dir.display()
+            ),
+            CredentialStore::Encrypted => encrypted_path(config_dir).display().to_string(),
+            CredentialStore::File => plaintext_path(config_dir).display().to_string(),
+        }
+    }
+
+    pub fn read(&self, config_dir: &Path) -> Result<Option<Vec<u8>>, GolemError> {
+        match self {
+            CredentialStore::Keyring => match keyring_entry(config_dir)?.get_password() {
+                Ok(data) => Ok(Some(data.into_bytes())),
+                Err(keyring::Error::NoEntry) => Ok(None),
+                Err(err) => Err(GolemError::new(format!(
+                    "Failed to read from the keyring: {err}. Choose another store with `auth login --store encrypted`, or `auth login --store file` to keep the token in plaintext"
+                ))),
+            },
+            CredentialStore::Encrypted => {
+                let path = encrypted_path(config_dir);
+                match read_file(&path)? {
+                    Some(bytes) => Ok(Some(decrypt(&bytes, &path)?)),
+                    None => Ok(None),
+                }
+            }
+            CredentialStore::File => read_file(&plaintext_path(config_dir)),
+        }
+    }
+
+    pub fn write(&self, config_dir: &Path, data: &[u8]) -> Result<(), GolemError> {
+        match self {
+            CredentialStore::Keyring => {
+                let data = String::from_utf8_lossy(data);
+                keyring_entry(config_dir)?
+                    .set_password(&data)
+                    .map_err(|e| GolemError::new(format!("Failed to write to the keyring: {e}")))
+            }
+            CredentialStore::Encrypted => {
+                write_private_file(&encrypted_path(config_dir), &encrypt(data)?)
+            }
+            CredentialStore::File => write_private_file(&plaintext_path(config_dir), data),
+        }
+    }
+
+    /// Removes the stored credentials, returning whether there were any.
+    pub fn delete(&self, config_dir: &Path) -> Result<bool, GolemError> {
+        match self {
+            CredentialStore::Keyring => match keyring_entry(config_dir)?.delete_password() {
+                Ok(()) => Ok(true),
+                Err(keyring::Error::NoEntry) => Ok(false),
+                Err(err) => {
+                    info!("Failed to delete from the keyring: {err}");
+                    Ok(false)
+                }
+            },
+            CredentialStore::Encrypted => remove_file(&encrypted_path(config_dir)),
+            CredentialStore::File => remove_file(&plaintext_path(config_dir)),
+        }
+    }
+}
@@ -76,5 +76,51 @@ pub fn action_cli_to_api(action: ProjectAction) -> golem_cloud_client::model::Pr
         ProjectAction::DeleteProjectGrants => {
             golem_cloud_client::model::ProjectAction::DeleteProjectGrants {}
         }
+        ProjectAction::ViewApiDefinition => {
+            golem_cloud_client::model::ProjectAction::ViewApiDefinition {}
+        }
+        ProjectAction::CreateApiDefinition => {
+            golem_cloud_client::model::ProjectAction::CreateApiDefinition {}
+        }
+        ProjectAction::UpdateApiDefinition => {
+            golem_cloud_client::model::ProjectAction::UpdateApiDefinition {}
+        }
+        ProjectAction::DeleteApiDefinition => {
+            golem_cloud_client::model::ProjectAction::DeleteApiDefinition {}
+        }
+    }
+}
+
+pub fn action_api_to_cli(action: golem_cloud_client::model::ProjectAction) -> ProjectAction {
+    match action {
+        golem_cloud_client::model::ProjectAction::ViewTemplate => ProjectAction::ViewTemplate,
+        golem_cloud_client::model::ProjectAction::CreateTemplate => ProjectAction::CreateTemplate,
+        golem_cloud_client::model::ProjectAction::UpdateTemplate => ProjectAction::UpdateTemplate,
+        golem_cloud_client::model::ProjectAction::DeleteTemplate => ProjectAction::DeleteTemplate,
+        golem_cloud_client::model::ProjectAction::ViewWorker => ProjectAction::ViewWorker,
+        golem_cloud_client::model::ProjectAction::CreateWorker => ProjectAction::CreateWorker,
+        golem_cloud_client::model::ProjectAction::UpdateWorker => ProjectAction::UpdateWorker,
+        golem_cloud_client::model::ProjectAction::DeleteWorker => ProjectAction::DeleteWorker,
+        golem_cloud_client::model::ProjectAction::ViewProjectGrants => {
+            ProjectAction::ViewProjectGrants
+        }
+        golem_cloud_client::model::ProjectAction::CreateProjectGrants => {
+            ProjectAction::CreateProjectGrants
+        }
+        golem_cloud_client::model::ProjectAction::DeleteProjectGrants => {
+            ProjectAction::DeleteProjectGrants
+        }
+        golem_cloud_client::model::ProjectAction::ViewApiDefinition => {
+            ProjectAction::ViewApiDefinition
+        }
+        golem_cloud_client::model::ProjectAction::CreateApiDefinition => {
+            ProjectAction::CreateApiDefinition
+        }
+        golem_cloud_client::model::ProjectAction::UpdateApiDefinition => {
+            ProjectAction::UpdateApiDefinition
+        }
+        golem_cloud_client::model::ProjectAction::DeleteApiDefinition => {
+            ProjectAction::DeleteApiDefinition
+        }
     }
 }
@@ -19,6 +19,8 @@ use golem_cloud_client::api::{
 
 pub trait ResponseContentErrorMapper {
     fn map(self) -> String;
+    /// HTTP status of the response
+    fn status(&self) -> u16;
 }
 
 impl ResponseContentErrorMapper for AccountError {
@@ -38,6 +40,14 @@ impl ResponseContentErrorMapper for AccountError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            AccountError::Error400(_) => 400,
+            AccountError::Error401(_) => 401,
+            AccountError::Error404(_) => 404,
+            AccountError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for GrantError {
@@ -57,6 +67,14 @@ impl ResponseContentErrorMapper for GrantError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            GrantError::Error400(_) => 400,
+            GrantError::Error401(_) => 401,
+            GrantError::Error404(_) => 404,
+            GrantError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for LoginError {
@@ -73,6 +91,13 @@ impl ResponseContentErrorMapper for LoginError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            LoginError::Error400(_) => 400,
+            LoginError::Error401(_) => 401,
+            LoginError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for ProjectError {
@@ -95,6 +120,15 @@ impl ResponseContentErrorMapper for ProjectError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ProjectError::Error400(_) => 400,
+            ProjectError::Error401(_) => 401,
+            ProjectError::Error403(_) => 403,
+            ProjectError::Error404(_) => 404,
+            ProjectError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for ProjectGrantError {
@@ -117,6 +151,15 @@ impl ResponseContentErrorMapper for ProjectGrantError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ProjectGrantError::Error400(_) => 400,
+            ProjectGrantError::Error401(_) => 401,
+            ProjectGrantError::Error403(_) => 403,
+            ProjectGrantError::Error404(_) => 404,
+            ProjectGrantError::Error500(_) => 500,
+        }
+    }
 }
 
 #[allow(unreachable_patterns)]
@@ -138,6 +181,14 @@ impl ResponseContentErrorMapper for ProjectPolicyError {
             _ => "UnknownError".into(),
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ProjectPolicyError::Error400(_) => 400,
+            ProjectPolicyError::Error401(_) => 401,
+            ProjectPolicyError::Error404(_) => 404,
+            ProjectPolicyError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for TemplateError {
@@ -163,6 +214,16 @@ impl ResponseContentErrorMapper for TemplateError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            TemplateError::Error400(_) => 400,
+            TemplateError::Error401(_) => 401,
+            TemplateError::Error403(_) => 403,
+            TemplateError::Error404(_) => 404,
+            TemplateError::Error409(_) => 409,
+            TemplateError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for TokenError {
@@ -182,6 +243,14 @@ impl ResponseContentErrorMapper for TokenError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            TokenError::Error400(_) => 400,
+            TokenError::Error401(_) => 401,
+            TokenError::Error404(_) => 404,
+            TokenError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for WorkerError {
@@ -207,4 +276,14 @@ impl ResponseContentErrorMapper for WorkerError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            WorkerError::Error400(_) => 400,
+            WorkerError::Error401(_) => 401,
+            WorkerError::Error403(_) => 403,
+            WorkerError::Error404(_) => 404,
+            WorkerError::Error409(_) => 409,
+            WorkerError::Error500(_) => 500,
+        }
+    }
 }
@@ -18,6 +18,8 @@ use golem_gateway_client::api::{
 
 pub trait ResponseContentErrorMapper {
     fn map(self) -> String;
+    /// HTTP status of the response
+    fn status(&self) -> u16;
 }
 
 impl ResponseContentErrorMapper for ApiCertificateError {
@@ -43,6 +45,16 @@ impl ResponseContentErrorMapper for ApiCertificateError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ApiCertificateError::Error400(_) => 400,
+            ApiCertificateError::Error401(_) => 401,
+            ApiCertificateError::Error403(_) => 403,
+            ApiCertificateError::Error404(_) => 404,
+            ApiCertificateError::Error409(_) => 409,
+            ApiCertificateError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for ApiDefinitionError {
@@ -68,6 +80,16 @@ impl ResponseContentErrorMapper for ApiDefinitionError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ApiDefinitionError::Error400(_) => 400,
+            ApiDefinitionError::Error401(_) => 401,
+            ApiDefinitionError::Error403(_) => 403,
+            ApiDefinitionError::Error404(_) => 404,
+            ApiDefinitionError::Error409(_) => 409,
+            ApiDefinitionError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for ApiDeploymentError {
@@ -93,6 +115,16 @@ impl ResponseContentErrorMapper for ApiDeploymentError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ApiDeploymentError::Error400(_) => 400,
+            ApiDeploymentError::Error401(_) => 401,
+            ApiDeploymentError::Error403(_) => 403,
+            ApiDeploymentError::Error404(_) => 404,
+            ApiDeploymentError::Error409(_) => 409,
+            ApiDeploymentError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for ApiDomainError {
@@ -118,10 +150,23 @@ impl ResponseContentErrorMapper for ApiDomainError {
             }
         }
     }
+    fn status(&self) -> u16 {
+        match self {
+            ApiDomainError::Error400(_) => 400,
+            ApiDomainError::Error401(_) => 401,
+            ApiDomainError::Error403(_) => 403,
+            ApiDomainError::Error404(_) => 404,
+            ApiDomainError::Error409(_) => 409,
+            ApiDomainError::Error500(_) => 500,
+        }
+    }
 }
 
 impl ResponseContentErrorMapper for HealthcheckError {
     fn map(self) -> String {
         match self {}
     }
+    fn status(&self) -> u16 {
+        match *self {}
+    }
 }
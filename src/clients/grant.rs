@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use async_trait::async_trait;
+use golem_cloud_client::api::GrantError;
 use tracing::info;
 
 use crate::model::{AccountId, GolemError, Role};
@@ -20,7 +21,7 @@ use crate::model::{AccountId, GolemError, Role};
 #[async_trait]
 pub trait GrantClient {
     async fn get_all(&self, account_id: AccountId) -> Result<Vec<Role>, GolemError>;
-    async fn get(&self, account_id: AccountId, role: Role) -> Result<Role, GolemError>;
+    async fn get(&self, account_id: AccountId, role: Role) -> Result<Option<Role>, GolemError>;
     async fn put(&self, account_id: AccountId, role: Role) -> Result<(), GolemError>;
     async fn delete(&self, account_id: AccountId, role: Role) -> Result<(), GolemError>;
 }
@@ -39,13 +40,15 @@ impl<C: golem_cloud_client::api::GrantClient + Sync + Send> GrantClient for Gran
         Ok(roles.into_iter().map(api_to_cli).collect())
     }
 
-    async fn get(&self, account_id: AccountId, role: Role) -> Result<Role, GolemError> {
+    async fn get(&self, account_id: AccountId, role: Role) -> Result<Option<Role>, GolemError> {
         info!("Getting account role.");
         let role = cli_to_api(role);
 
-        Ok(api_to_cli(
-            self.client.role_get(&account_id.id, &role).await?,
-        ))
+        match self.client.role_get(&account_id.id, &role).await {
+            Ok(role) => Ok(Some(api_to_cli(role))),
+            Err(golem_cloud_client::Error::Item(GrantError::Error404(_))) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
     }
 
     async fn put(&self, account_id: AccountId, role: Role) -> Result<(), GolemError> {
@@ -69,22 +72,22 @@ impl<C: golem_cloud_client::api::GrantClient + Sync + Send> GrantClient for Gran
 
 fn api_to_cli(role: golem_cloud_client::model::Role) -> Role {
     match role {
-        golem_cloud_client::model::Role::Admin {} => Role::Admin,
-        golem_cloud_client::model::Role::MarketingAdmin {} => Role::MarketingAdmin,
-        golem_cloud_client::model::Role::ViewProject {} => Role::ViewProject,
-        golem_cloud_client::model::Role::DeleteProject {} => Role::DeleteProject,
-        golem_cloud_client::model::Role::CreateProject {} => Role::CreateProject,
-        golem_cloud_client::model::Role::InstanceServer {} => Role::InstanceServer,
+        golem_cloud_client::model::Role::Admin => Role::Admin,
+        golem_cloud_client::model::Role::MarketingAdmin => Role::MarketingAdmin,
+        golem_cloud_client::model::Role::ViewProject => Role::ViewProject,
+        golem_cloud_client::model::Role::DeleteProject => Role::DeleteProject,
+        golem_cloud_client::model::Role::CreateProject => Role::CreateProject,
+        golem_cloud_client::model::Role::InstanceServer => Role::InstanceServer,
     }
 }
 
 fn cli_to_api(role: Role) -> golem_cloud_client::model::Role {
     match role {
-        Role::Admin {} => golem_cloud_client::model::Role::Admin,
-        Role::MarketingAdmin {} => golem_cloud_client::model::Role::MarketingAdmin,
-        Role::ViewProject {} => golem_cloud_client::model::Role::ViewProject,
-        Role::DeleteProject {} => golem_cloud_client::model::Role::DeleteProject,
-        Role::CreateProject {} => golem_cloud_client::model::Role::CreateProject,
-        Role::InstanceServer {} => golem_cloud_client::model::Role::InstanceServer,
+        Role::Admin => golem_cloud_client::model::Role::Admin,
+        Role::MarketingAdmin => golem_cloud_client::model::Role::MarketingAdmin,
+        Role::ViewProject => golem_cloud_client::model::Role::ViewProject,
+        Role::DeleteProject => golem_cloud_client::model::Role::DeleteProject,
+        Role::CreateProject => golem_cloud_client::model::Role::CreateProject,
+        Role::InstanceServer => golem_cloud_client::model::Role::InstanceServer,
     }
 }
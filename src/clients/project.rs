@@ -29,6 +29,7 @@ pub trait ProjectClient {
     ) -> Result<Project, GolemError>;
     async fn find(&self, name: Option<String>) -> Result<Vec<Project>, GolemError>;
     async fn find_default(&self) -> Result<Project, GolemError>;
+    async fn get(&self, project_id: ProjectId) -> Result<Project, GolemError>;
     async fn delete(&self, project_id: ProjectId) -> Result<(), GolemError>;
 
     async fn resolve_id(&self, project_ref: ProjectRef) -> Result<Option<ProjectId>, GolemError>;
@@ -46,6 +47,8 @@ pub trait ProjectClient {
 
 pub struct ProjectClientLive<C: golem_cloud_client::api::ProjectClient + Sync + Send> {
     pub client: C,
+    /// Project pinned with `project use`, resolved locally instead of asking for the default
+    pub default_project: Option<ProjectId>,
 }
 
 #[async_trait]
@@ -80,6 +83,12 @@ impl<C: golem_cloud_client::api::ProjectClient + Sync + Send> ProjectClient
         Ok(self.client.default_get().await?)
     }
 
+    async fn get(&self, project_id: ProjectId) -> Result<Project, GolemError> {
+        info!("Getting project {project_id:?}");
+
+        Ok(self.client.project_id_get(&project_id.0).await?)
+    }
+
     async fn delete(&self, project_id: ProjectId) -> Result<(), GolemError> {
         info!("Deleting project {project_id:?}");
 
@@ -97,7 +106,7 @@ impl<C: golem_cloud_client::api::ProjectClient + Sync + Send> ProjectClient
                 if projects.len() > 1 {
                     let projects: Vec<String> =
                         projects.iter().map(|p| p.project_id.to_string()).collect();
-                    Err(GolemError(formatdoc!(
+                    Err(GolemError::new(formatdoc!(
                         "
                             Multiple projects found for name {name}:
                             {}
@@ -107,12 +116,14 @@ impl<C: golem_cloud_client::api::ProjectClient + Sync + Send> ProjectClient
                     )))
                 } else {
                     match projects.first() {
-                        None => Err(GolemError(format!("Can't find project with name {name}"))),
+                        None => Err(GolemError::new(format!(
+                            "Can't find project with name {name}"
+                        ))),
                         Some(project) => Ok(Some(ProjectId(project.project_id))),
                     }
                 }
             }
-            ProjectRef::Default => Ok(None),
+            ProjectRef::Default => Ok(self.default_project.clone()),
         }
     }
 }
@@ -17,7 +17,9 @@ use golem_cloud_client::model::{ProjectGrant, ProjectGrantDataRequest};
 use tracing::info;
 
 use crate::clients::action_cli_to_api;
-use crate::model::{AccountId, GolemError, ProjectAction, ProjectId, ProjectPolicyId};
+use crate::model::{
+    AccountId, GolemError, ProjectAction, ProjectGrantId, ProjectId, ProjectPolicyId,
+};
 
 #[async_trait]
 pub trait ProjectGrantClient {
@@ -32,7 +34,14 @@ pub trait ProjectGrantClient {
         project_id: ProjectId,
         account_id: AccountId,
         actions: Vec<ProjectAction>,
+        policy_name: Option<String>,
     ) -> Result<ProjectGrant, GolemError>;
+    async fn find(&self, project_id: ProjectId) -> Result<Vec<ProjectGrant>, GolemError>;
+    async fn delete(
+        &self,
+        project_id: ProjectId,
+        grant_id: ProjectGrantId,
+    ) -> Result<(), GolemError>;
 }
 
 pub struct ProjectGrantClientLive<C: golem_cloud_client::api::ProjectGrantClient + Sync + Send> {
@@ -66,16 +75,38 @@ impl<C: golem_cloud_client::api::ProjectGrantClient + Sync + Send> ProjectGrantC
         project_id: ProjectId,
         account_id: AccountId,
         actions: Vec<ProjectAction>,
+        policy_name: Option<String>,
     ) -> Result<ProjectGrant, GolemError> {
         info!("Creating project grant for actions.");
 
         let data = ProjectGrantDataRequest {
             grantee_account_id: account_id.id,
             project_policy_id: None,
-            project_policy_name: None,
+            project_policy_name: policy_name,
             project_actions: actions.into_iter().map(action_cli_to_api).collect(),
         };
 
         Ok(self.client.post(&project_id.0, &data).await?)
     }
+
+    async fn find(&self, project_id: ProjectId) -> Result<Vec<ProjectGrant>, GolemError> {
+        info!("Listing grants of project {project_id:?}.");
+
+        Ok(self.client.get(&project_id.0).await?)
+    }
+
+    async fn delete(
+        &self,
+        project_id: ProjectId,
+        grant_id: ProjectGrantId,
+    ) -> Result<(), GolemError> {
+        info!("Deleting project grant {grant_id}.");
+
+        let _ = self
+            .client
+            .grant_id_delete(&project_id.0, &grant_id.0)
+            .await?;
+
+        Ok(())
+    }
 }
@@ -25,6 +25,7 @@ use tokio::fs::File;
 use tracing::info;
 
 use crate::model::{GolemError, PathBufOrStdin, TemplateName};
+use crate::table::TableColumns;
 use crate::{ProjectId, RawTemplateId};
 
 #[async_trait]
@@ -45,6 +46,14 @@ pub trait TemplateClient {
         id: RawTemplateId,
         file: PathBufOrStdin,
     ) -> Result<TemplateView, GolemError>;
+    async fn get_metadata(&self, id: &RawTemplateId, version: i32) -> Result<Template, GolemError>;
+    async fn get_latest_metadata(&self, id: &RawTemplateId) -> Result<Template, GolemError>;
+    async fn get_versions(&self, id: &RawTemplateId) -> Result<Vec<Template>, GolemError>;
+    async fn download(
+        &self,
+        id: &RawTemplateId,
+        version: Option<i32>,
+    ) -> Result<Vec<u8>, GolemError>;
 }
 
 #[derive(Clone)]
@@ -62,6 +71,15 @@ pub struct TemplateView {
     pub exports: Vec<String>,
 }
 
+impl TableColumns for TemplateView {
+    const COLUMNS: &'static [&'static str] = &[
+        "templateId",
+        "templateName",
+        "templateVersion",
+        "templateSize",
+    ];
+}
+
 impl From<&Template> for TemplateView {
     fn from(value: &Template) -> Self {
         TemplateView {
@@ -101,7 +119,40 @@ impl From<&Template> for TemplateView {
     }
 }
 
-fn render_type(typ: &Type) -> String {
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateVersionView {
+    pub template_version: i32,
+    pub template_size: i32,
+    pub exported_functions: usize,
+    pub latest: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers: Option<usize>,
+}
+
+impl TableColumns for TemplateVersionView {}
+
+impl From<&Template> for TemplateVersionView {
+    fn from(value: &Template) -> Self {
+        TemplateVersionView {
+            template_version: value.versioned_template_id.version,
+            template_size: value.template_size,
+            exported_functions: value
+                .metadata
+                .exports
+                .iter()
+                .map(|exp| match exp {
+                    Export::Instance(ExportInstance { functions, .. }) => functions.len(),
+                    Export::Function(_) => 1,
+                })
+                .sum(),
+            latest: false,
+            workers: None,
+        }
+    }
+}
+
+pub fn render_type(typ: &Type) -> String {
     match typ {
         Type::Variant(TypeVariant { cases }) => {
             let cases_str = cases
@@ -172,7 +223,7 @@ fn render_result(r: &FunctionResult) -> String {
     }
 }
 
-fn show_exported_function(
+pub fn show_exported_function(
     prefix: &str,
     name: &str,
     parameters: &[FunctionParameter],
@@ -230,7 +281,7 @@ impl<C: golem_cloud_client::api::TemplateClient + Sync + Send> TemplateClient
             PathBufOrStdin::Path(path) => {
                 let file = File::open(path)
                     .await
-                    .map_err(|e| GolemError(format!("Can't open template file: {e}")))?;
+                    .map_err(|e| GolemError::new(format!("Can't open template file: {e}")))?;
 
                 self.client.post(&query, file).await?
             }
@@ -239,7 +290,7 @@ impl<C: golem_cloud_client::api::TemplateClient + Sync + Send> TemplateClient
 
                 let _ = std::io::stdin()
                     .read_to_end(&mut bytes) // TODO: steaming request from stdin
-                    .map_err(|e| GolemError(format!("Failed to read stdin: {e:?}")))?;
+                    .map_err(|e| GolemError::new(format!("Failed to read stdin: {e:?}")))?;
 
                 self.client.post(&query, bytes).await?
             }
@@ -259,7 +310,7 @@ impl<C: golem_cloud_client::api::TemplateClient + Sync + Send> TemplateClient
             PathBufOrStdin::Path(path) => {
                 let file = File::open(path)
                     .await
-                    .map_err(|e| GolemError(format!("Can't open template file: {e}")))?;
+                    .map_err(|e| GolemError::new(format!("Can't open template file: {e}")))?;
 
                 self.client.template_id_upload_put(&id.0, file).await?
             }
@@ -268,7 +319,7 @@ impl<C: golem_cloud_client::api::TemplateClient + Sync + Send> TemplateClient
 
                 let _ = std::io::stdin()
                     .read_to_end(&mut bytes) // TODO: steaming request from stdin
-                    .map_err(|e| GolemError(format!("Failed to read stdin: {e:?}")))?;
+                    .map_err(|e| GolemError::new(format!("Failed to read stdin: {e:?}")))?;
 
                 self.client.template_id_upload_put(&id.0, bytes).await?
             }
@@ -276,4 +327,41 @@ impl<C: golem_cloud_client::api::TemplateClient + Sync + Send> TemplateClient
 
         Ok((&template).into())
     }
+
+    async fn get_metadata(&self, id: &RawTemplateId, version: i32) -> Result<Template, GolemError> {
+        info!("Getting template {id:?} version {version}");
+
+        let templates = self.client.template_id_get(&id.0).await?;
+
+        templates
+            .into_iter()
+            .find(|t| t.versioned_template_id.version == version)
+            .ok_or_else(|| {
+                GolemError::new(format!("Can't find version {version} of template {}", id.0))
+            })
+    }
+
+    async fn get_latest_metadata(&self, id: &RawTemplateId) -> Result<Template, GolemError> {
+        info!("Getting latest version of template {id:?}");
+
+        Ok(self.client.template_id_latest_get(&id.0).await?)
+    }
+
+    async fn get_versions(&self, id: &RawTemplateId) -> Result<Vec<Template>, GolemError> {
+        info!("Getting versions of template {id:?}");
+
+        Ok(self.client.template_id_get(&id.0).await?)
+    }
+
+    async fn download(
+        &self,
+        id: &RawTemplateId,
+        version: Option<i32>,
+    ) -> Result<Vec<u8>, GolemError> {
+        info!("Downloading template {id:?} version {version:?}");
+
+        let bytes = self.client.template_id_download_get(&id.0, version).await?;
+
+        Ok(bytes.to_vec())
+    }
 }
@@ -16,6 +16,7 @@ use std::time::Duration;
 
 use async_trait::async_trait;
 use futures_util::{future, pin_mut, SinkExt, StreamExt};
+use golem_cloud_client::api::WorkerError;
 use golem_cloud_client::model::{
     CallingConvention, InvokeParameters, InvokeResult, VersionedWorkerId, WorkerCreationRequest,
     WorkerMetadata,
@@ -81,6 +82,11 @@ pub trait WorkerClient {
         name: WorkerName,
         template_id: RawTemplateId,
     ) -> Result<WorkerMetadata, GolemError>;
+    async fn find_metadata(
+        &self,
+        name: WorkerName,
+        template_id: RawTemplateId,
+    ) -> Result<Option<WorkerMetadata>, GolemError>;
     async fn connect(&self, name: WorkerName, template_id: RawTemplateId)
         -> Result<(), GolemError>;
 }
@@ -228,6 +234,20 @@ impl<C: golem_cloud_client::api::WorkerClient + Sync + Send> WorkerClient for Wo
         Ok(self.client.worker_name_get(&template_id.0, &name.0).await?)
     }
 
+    async fn find_metadata(
+        &self,
+        name: WorkerName,
+        template_id: RawTemplateId,
+    ) -> Result<Option<WorkerMetadata>, GolemError> {
+        info!("Looking up worker {}/{}", template_id.0, name.0);
+
+        match self.client.worker_name_get(&template_id.0, &name.0).await {
+            Ok(metadata) => Ok(Some(metadata)),
+            Err(golem_cloud_client::Error::Item(WorkerError::Error404(_))) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
     async fn connect(
         &self,
         name: WorkerName,
@@ -238,10 +258,10 @@ impl<C: golem_cloud_client::api::WorkerClient + Sync + Send> WorkerClient for Wo
         let ws_schema = if url.scheme() == "http" { "ws" } else { "wss" };
 
         url.set_scheme(ws_schema)
-            .map_err(|_| GolemError("Can't set schema.".to_string()))?;
+            .map_err(|_| GolemError::new("Can't set schema.".to_string()))?;
 
         url.path_segments_mut()
-            .map_err(|_| GolemError("Can't get path.".to_string()))?
+            .map_err(|_| GolemError::new("Can't get path.".to_string()))?
             .push("v2")
             .push("templates")
             .push(&template_id.0.to_string())
@@ -251,7 +271,7 @@ impl<C: golem_cloud_client::api::WorkerClient + Sync + Send> WorkerClient for Wo
 
         let mut request = url
             .into_client_request()
-            .map_err(|e| GolemError(format!("Can't create request: {e}")))?;
+            .map_err(|e| GolemError::new(format!("Can't create request: {e}")))?;
         let headers = request.headers_mut();
 
         if let Some(token) = self.context.bearer_token() {
@@ -278,18 +298,18 @@ impl<C: golem_cloud_client::api::WorkerClient + Sync + Send> WorkerClient for Wo
             .map_err(|e| match e {
                 tungstenite::error::Error::Http(http_error_response) => {
                     match http_error_response.body().clone() {
-                        Some(body) => GolemError(format!(
+                        Some(body) => GolemError::new(format!(
                             "Failed Websocket. Http error: {}, {}",
                             http_error_response.status(),
                             String::from_utf8_lossy(&body)
                         )),
-                        None => GolemError(format!(
+                        None => GolemError::new(format!(
                             "Failed Websocket. Http error: {}",
                             http_error_response.status()
                         )),
                     }
                 }
-                _ => GolemError(format!("Failed Websocket. Error: {}", e)),
+                _ => GolemError::new(format!("Failed Websocket. Error: {}", e)),
             })?;
 
         let (mut write, read) = ws_stream.split();
@@ -305,7 +325,7 @@ impl<C: golem_cloud_client::api::WorkerClient + Sync + Send> WorkerClient for Wo
                 write
                     .send(Message::Ping(cnt.to_ne_bytes().to_vec()))
                     .await
-                    .unwrap(); // TODO: handle errors: map_err(|e| GolemError(format!("Ping failure: {e}")))?;
+                    .unwrap(); // TODO: handle errors: map_err(|e| GolemError::new(format!("Ping failure: {e}")))?;
 
                 cnt += 1;
             }
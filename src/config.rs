@@ -0,0 +1,155 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::auth::store::{write_private_file, CredentialStore};
+use crate::model::{Format, GolemError, ProjectId};
+
+/// Local CLI settings, kept next to the stored authentication.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Config {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<PinnedProject>,
+    /// Profile used when `--profile` is not given
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, Profile>,
+    /// Where OAuth2 tokens are kept, the OS keyring when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_store: Option<CredentialStore>,
+}
+
+/// Settings for one Golem Cloud environment and account.
+///
+/// Each profile keeps its stored authentication in its own directory, see [Config::profile_dir].
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_token: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<PinnedProject>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+}
+
+/// Profile names become directory names, so they are limited to letters, digits, `-` and `_`.
+pub fn parse_profile_name(s: &str) -> Result<String, String> {
+    if !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        Ok(s.to_string())
+    } else {
+        Err(format!(
+            "Invalid profile name: {s}. Expected letters, digits, \"-\" and \"_\""
+        ))
+    }
+}
+
+/// Project used instead of the account's default project when none is given.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PinnedProject {
+    pub id: Uuid,
+    pub name: String,
+}
+
+impl Config {
+    pub fn path(config_dir: &Path) -> PathBuf {
+        config_dir.join("config.json")
+    }
+
+    pub fn read(config_dir: &Path) -> Result<Config, GolemError> {
+        let path = Config::path(config_dir);
+
+        match File::open(&path) {
+            Ok(file) => serde_json::from_reader(BufReader::new(file))
+                .map_err(|e| GolemError::new(format!("Failed to parse config {path:?}: {e}"))),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Config::default()),
+            Err(err) => Err(GolemError::new(format!(
+                "Failed to open config {path:?}: {err}"
+            ))),
+        }
+    }
+
+    /// Profiles can hold tokens, so the config is only readable by the user.
+    pub fn store(&self, config_dir: &Path) -> Result<(), GolemError> {
+        let path = Config::path(config_dir);
+
+        let json = serde_json::to_vec_pretty(self)
+            .map_err(|e| GolemError::new(format!("Failed to write config {path:?}: {e}")))?;
+
+        write_private_file(&path, &json)
+    }
+
+    pub fn profiles_dir(config_dir: &Path) -> PathBuf {
+        config_dir.join("profiles")
+    }
+
+    pub fn profile_dir(config_dir: &Path, name: &str) -> Result<PathBuf, GolemError> {
+        parse_profile_name(name).map_err(GolemError::new)?;
+
+        Ok(Config::profiles_dir(config_dir).join(name))
+    }
+
+    pub fn profile(&self, name: &str) -> Result<&Profile, GolemError> {
+        self.profiles
+            .get(name)
+            .ok_or_else(|| GolemError::new(format!("Unknown profile {name}")))
+    }
+
+    /// The pinned project of a profile, or the one pinned outside of profiles.
+    pub fn pinned_project(&self, profile: Option<&str>) -> Option<&PinnedProject> {
+        match profile {
+            Some(name) => self.profiles.get(name).and_then(|p| p.project.as_ref()),
+            None => self.project.as_ref(),
+        }
+    }
+
+    pub fn pin_project(
+        &mut self,
+        profile: Option<&str>,
+        project: Option<PinnedProject>,
+    ) -> Result<(), GolemError> {
+        match profile {
+            Some(name) => {
+                self.profiles
+                    .get_mut(name)
+                    .ok_or_else(|| GolemError::new(format!("Unknown profile {name}")))?
+                    .project = project
+            }
+            None => self.project = project,
+        }
+
+        Ok(())
+    }
+
+    pub fn project_id(&self, profile: Option<&str>) -> Option<ProjectId> {
+        self.pinned_project(profile).map(|p| ProjectId(p.id))
+    }
+}
@@ -76,7 +76,7 @@ fn read_as_string<R: Read>(mut r: R, source: &str) -> Result<String, GolemError>
     let mut result = String::new();
 
     r.read_to_string(&mut result)
-        .map_err(|e| GolemError(format!("Failed to read {source} as String: ${e}")))?;
+        .map_err(|e| GolemError::new(format!("Failed to read {source} as String: ${e}")))?;
 
     Ok(result)
 }
@@ -85,7 +85,7 @@ fn read_path_or_stdin_as_string(path_or_stdin: PathBufOrStdin) -> Result<String,
     match path_or_stdin {
         PathBufOrStdin::Path(path) => {
             let file = File::open(&path)
-                .map_err(|e| GolemError(format!("Failed to open file {path:?}: {e}")))?;
+                .map_err(|e| GolemError::new(format!("Failed to open file {path:?}: {e}")))?;
 
             let reader = BufReader::new(file);
 
@@ -73,15 +73,20 @@ fn read_definition<R: Read>(
 ) -> Result<ApiDefinition, GolemError> {
     let api_definition: ApiDefinition = match format {
         Format::Json => serde_json::from_reader(r).map_err(|e| {
-            GolemError(format!(
+            GolemError::new(format!(
                 "Failed to parse ApiDefinition from {source} as json: ${e}"
             ))
         })?,
         Format::Yaml => serde_yaml::from_reader(r).map_err(|e| {
-            GolemError(format!(
+            GolemError::new(format!(
                 "Failed to parse ApiDefinition from {source} as yaml: ${e}"
             ))
         })?,
+        Format::Table => {
+            return Err(GolemError::new(format!(
+                "Can't read ApiDefinition from {source} as a table, use json or yaml"
+            )))
+        }
     };
 
     Ok(api_definition)
@@ -114,7 +119,7 @@ impl<'p, C: DefinitionClient + Sync + Send, P: ProjectClient + Sync + Send> Defi
                 let definition = match definition_file.unwrap_or(PathBufOrStdin::Stdin) {
                     PathBufOrStdin::Path(path) => {
                         let file = File::open(&path).map_err(|e| {
-                            GolemError(format!("Failed to open file {path:?}: {e}"))
+                            GolemError::new(format!("Failed to open file {path:?}: {e}"))
                         })?;
 
                         let reader = BufReader::new(file);
@@ -15,9 +15,10 @@
 extern crate derive_more;
 
 use std::fmt::Debug;
-use std::path::PathBuf;
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
 
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
 use clap_verbosity_flag::{Level, Verbosity};
 use golem_cloud_client::{Context, Security};
 use golem_examples::model::{ExampleName, GuestLanguage, GuestLanguageTier, PackageName};
@@ -28,7 +29,7 @@ use tracing_subscriber::FmtSubscriber;
 use uuid::Uuid;
 
 use crate::account::{AccountHandler, AccountHandlerLive, AccountSubcommand};
-use crate::auth::{Auth, AuthLive};
+use crate::auth::{Auth, AuthHandlerLive, AuthLive, AuthSubcommand, LoginPrompt};
 use crate::clients::account::AccountClientLive;
 use crate::clients::grant::GrantClientLive;
 use crate::clients::login::LoginClientLive;
@@ -38,10 +39,14 @@ use crate::clients::project_grant::ProjectGrantClientLive;
 use crate::clients::template::TemplateClientLive;
 use crate::clients::token::TokenClientLive;
 use crate::clients::worker::WorkerClientLive;
+use crate::config::{parse_profile_name, Config};
 use crate::gateway::{GatewayHandler, GatewayHandlerLive, GatewaySubcommand};
+use crate::manifest::{ManifestHandler, ManifestHandlerLive, ManifestSubcommand};
 use crate::policy::{ProjectPolicyHandler, ProjectPolicyHandlerLive, ProjectPolicySubcommand};
+use crate::profile::{ProfileHandler, ProfileHandlerLive, ProfileSubcommand};
 use crate::project::{ProjectHandler, ProjectHandlerLive, ProjectSubcommand};
 use crate::project_grant::{ProjectGrantHandler, ProjectGrantHandlerLive};
+use crate::query::Query;
 use crate::template::{TemplateHandler, TemplateHandlerLive, TemplateSubcommand};
 use crate::token::{TokenHandler, TokenHandlerLive, TokenSubcommand};
 use crate::worker::{WorkerHandler, WorkerHandlerLive, WorkerSubcommand};
@@ -49,12 +54,17 @@ use crate::worker::{WorkerHandler, WorkerHandlerLive, WorkerSubcommand};
 mod account;
 mod auth;
 pub mod clients;
+mod config;
 mod examples;
 mod gateway;
+mod manifest;
 pub mod model;
 mod policy;
+mod profile;
 mod project;
 mod project_grant;
+mod query;
+mod table;
 mod template;
 mod token;
 mod worker;
@@ -68,6 +78,30 @@ pub fn parse_key_val(
     Ok((s[..pos].parse()?, s[pos + 1..].parse()?))
 }
 
+/// Asks a yes/no question on stderr, so it doesn't mix with the command's output.
+///
+/// Fails without asking when stdin is not a terminal, so scripts have to pass --yes instead of
+/// silently getting "no" at the end of their input.
+pub fn confirm(question: &str) -> Result<bool, GolemError> {
+    if !std::io::stdin().is_terminal() {
+        return Err(GolemError::new(format!(
+            "{question} Can't ask for confirmation as stdin is not a terminal, pass --yes to confirm"
+        )));
+    }
+
+    eprint!("{question} [y/N] ");
+    std::io::stderr()
+        .flush()
+        .map_err(|e| GolemError::new(format!("Failed to write prompt: {e}")))?;
+
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| GolemError::new(format!("Failed to read answer: {e}")))?;
+
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
 #[derive(Subcommand, Debug)]
 #[command()]
 enum Command {
@@ -137,6 +171,10 @@ enum Command {
             conflicts_with = "project_policy_id"
         )]
         project_actions: Option<Vec<ProjectAction>>,
+
+        /// Name of the sharing policy created for `--project-actions`
+        #[arg(long, requires = "project_actions")]
+        project_policy_name: Option<String>,
     },
 
     /// Manage project sharing policies
@@ -177,6 +215,27 @@ enum Command {
         subcommand: GatewaySubcommand,
     },
 
+    /// Plan and apply a declarative project manifest
+    #[command()]
+    Manifest {
+        #[command(subcommand)]
+        subcommand: ManifestSubcommand,
+    },
+
+    /// Log in, log out and show the authentication in use
+    #[command()]
+    Auth {
+        #[command(subcommand)]
+        subcommand: AuthSubcommand,
+    },
+
+    /// Manage profiles for different Golem Cloud environments and accounts
+    #[command()]
+    Profile {
+        #[command(subcommand)]
+        subcommand: ProfileSubcommand,
+    },
+
     /// WASM RPC stub generator
     #[cfg(feature = "stubgen")]
     Stubgen {
@@ -191,14 +250,35 @@ struct GolemCommand {
     #[arg(short = 'D', long, value_name = "DIR", value_hint = clap::ValueHint::DirPath)]
     config_directory: Option<PathBuf>,
 
-    #[arg(short = 'T', long)]
+    #[arg(short = 'T', long, conflicts_with = "auth_token_file")]
     auth_token: Option<Uuid>, // TODO: uuid
 
+    /// File containing the token to authenticate with, instead of passing it with -T.
+    /// The GOLEM_CLOUD_TOKEN environment variable can be used as well.
+    #[arg(long, value_name = "FILE", value_hint = clap::ValueHint::FilePath)]
+    auth_token_file: Option<PathBuf>,
+
+    /// Open the OAuth2 login page in a browser when logging in
+    #[arg(long, global = true)]
+    open_browser: bool,
+
+    /// Seconds to wait for the OAuth2 login, by default until the login code expires
+    #[arg(long, global = true, value_name = "SECONDS")]
+    login_timeout: Option<u64>,
+
+    /// Profile to use instead of the active one, its URLs win over the GOLEM_*BASE_URL variables
+    #[arg(long, global = true, value_parser = parse_profile_name)]
+    profile: Option<String>,
+
     #[command(flatten)]
     verbosity: Verbosity,
 
-    #[arg(short = 'F', long, default_value = "yaml")]
-    format: Format,
+    /// Output format, defaults to the profile's format or yaml
+    #[arg(short = 'F', long)]
+    format: Option<Format>,
+
+    #[command(flatten)]
+    output: OutputArgs,
 
     #[command(subcommand)]
     command: Command,
@@ -225,24 +305,51 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             .expect("setting default subscriber failed");
     }
 
-    tokio::runtime::Builder::new_multi_thread()
+    let result = tokio::runtime::Builder::new_multi_thread()
         .enable_all()
         .build()
         .unwrap()
-        .block_on(async_main(command))
+        .block_on(async_main(command));
+
+    if let Err(err) = &result {
+        if let Some(err) = err.downcast_ref::<GolemError>() {
+            if err.is_not_authenticated() {
+                eprintln!("Error: {err}");
+                std::process::exit(EXIT_NOT_AUTHENTICATED);
+            }
+        }
+    }
+
+    result
+}
+
+/// The token given on the command line, in a file or in the environment, in that order.
+fn given_token(
+    auth_token: Option<Uuid>,
+    auth_token_file: Option<&Path>,
+) -> Result<Option<Uuid>, GolemError> {
+    if let Some(token) = auth_token {
+        return Ok(Some(token));
+    }
+
+    if let Some(path) = auth_token_file {
+        let token = std::fs::read_to_string(path)
+            .map_err(|e| GolemError::new(format!("Failed to read token file {path:?}: {e}")))?;
+        return Uuid::parse_str(token.trim())
+            .map(Some)
+            .map_err(|e| GolemError::new(format!("Invalid token in {path:?}: {e}")));
+    }
+
+    match std::env::var("GOLEM_CLOUD_TOKEN") {
+        Ok(token) => Uuid::parse_str(token.trim())
+            .map(Some)
+            .map_err(|e| GolemError::new(format!("Invalid token in GOLEM_CLOUD_TOKEN: {e}"))),
+        Err(_) => Ok(None),
+    }
 }
 
 async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>> {
-    let url_str = std::env::var("GOLEM_CLOUD_BASE_URL")
-        .ok()
-        .or_else(|| std::env::var("GOLEM_BASE_URL").ok())
-        .unwrap_or("https://release.api.golem.cloud/".to_string());
-    let gateway_url_str = std::env::var("GOLEM_GATEWAY_BASE_URL").unwrap_or(url_str.clone());
-    let url = Url::parse(&url_str).unwrap();
-    let gateway_url = Url::parse(&gateway_url_str).unwrap();
     let home = dirs::home_dir().unwrap();
-    let allow_insecure_str = std::env::var("GOLEM_ALLOW_INSECURE").unwrap_or("false".to_string());
-    let allow_insecure = allow_insecure_str != "false";
     let default_conf_dir = home.join(".golem");
 
     debug!(
@@ -250,6 +357,48 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
         default_conf_dir.display()
     );
 
+    let config_dir = cmd.config_directory.clone().unwrap_or(default_conf_dir);
+    let config = Config::read(&config_dir)?;
+
+    // Profiles are managed before authenticating, so a broken profile can still be fixed
+    let command = match cmd.command {
+        Command::Profile { subcommand } => {
+            let profile_srv = ProfileHandlerLive {
+                config_dir: config_dir.clone(),
+            };
+            let res = profile_srv.handle(subcommand).await;
+
+            return print_result(res, &cmd.output.output(cmd.format.unwrap_or(Format::Yaml)));
+        }
+        command => command,
+    };
+
+    let profile_name = cmd.profile.clone().or(config.active_profile.clone());
+    let profile = match &profile_name {
+        Some(name) => config.profile(name)?.clone(),
+        None => Default::default(),
+    };
+    let format = cmd.format.or(profile.format).unwrap_or(Format::Yaml);
+    let output = cmd.output.output(format);
+
+    // An explicitly chosen profile wins over the environment, so its token is only ever sent
+    // to its own servers
+    let env_var = |name: &str| match cmd.profile {
+        Some(_) => None,
+        None => std::env::var(name).ok(),
+    };
+    let url_str = env_var("GOLEM_CLOUD_BASE_URL")
+        .or_else(|| env_var("GOLEM_BASE_URL"))
+        .or(profile.base_url.clone())
+        .unwrap_or("https://release.api.golem.cloud/".to_string());
+    let gateway_url_str = env_var("GOLEM_GATEWAY_BASE_URL")
+        .or(profile.gateway_url.clone())
+        .unwrap_or(url_str.clone());
+    let url = Url::parse(&url_str).unwrap();
+    let gateway_url = Url::parse(&gateway_url_str).unwrap();
+    let allow_insecure_str = std::env::var("GOLEM_ALLOW_INSECURE").unwrap_or("false".to_string());
+    let allow_insecure = allow_insecure_str != "false";
+
     let mut builder = reqwest::Client::builder();
     if allow_insecure {
         builder = builder.danger_accept_invalid_certs(true);
@@ -266,15 +415,44 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
         client: golem_cloud_client::api::LoginClientLive {
             context: login_context.clone(),
         },
-        context: login_context,
+        context: login_context.clone(),
+    };
+    let auth_srv = AuthLive {
+        login,
+        store: config.credential_store.unwrap_or_default(),
+        prompt: LoginPrompt {
+            format,
+            open_browser: cmd.open_browser,
+            timeout: cmd.login_timeout.map(std::time::Duration::from_secs),
+        },
+    };
+
+    // Each profile has its own stored authentication
+    let auth_dir = match &profile_name {
+        Some(name) => Config::profile_dir(&config_dir, name)?,
+        None => config_dir.clone(),
+    };
+    let manual_token =
+        given_token(cmd.auth_token, cmd.auth_token_file.as_deref())?.or(profile.auth_token);
+
+    let command = match command {
+        Command::Auth { subcommand } => {
+            let auth_handler = AuthHandlerLive {
+                auth: &auth_srv,
+                context: login_context,
+                config_dir: config_dir.clone(),
+                auth_dir,
+                manual_token,
+            };
+            let res = auth_handler.handle(subcommand).await;
+
+            return print_result(res, &output);
+        }
+        command => command,
     };
-    let auth_srv = AuthLive { login };
 
     let auth = auth_srv
-        .authenticate(
-            cmd.auth_token,
-            cmd.config_directory.clone().unwrap_or(default_conf_dir),
-        )
+        .authenticate(manual_token, auth_dir.clone())
         .await?;
 
     let context = Context {
@@ -293,10 +471,6 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
             context: context.clone(),
         },
     };
-    let acc_srv = AccountHandlerLive {
-        client: account_client,
-        grant: grant_client,
-    };
     let token_client = TokenClientLive {
         client: golem_cloud_client::api::TokenClientLive {
             context: context.clone(),
@@ -304,14 +478,52 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
     };
     let token_srv = TokenHandlerLive {
         client: token_client,
+        config_dir: config_dir.clone(),
     };
     let project_client = ProjectClientLive {
         client: golem_cloud_client::api::ProjectClientLive {
             context: context.clone(),
         },
+        default_project: config.project_id(profile_name.as_deref()),
+    };
+    let acc_srv = AccountHandlerLive {
+        client: account_client,
+        grant: grant_client,
+        projects: &project_client,
+        templates: TemplateClientLive {
+            client: golem_cloud_client::api::TemplateClientLive {
+                context: context.clone(),
+            },
+        },
     };
     let project_srv = ProjectHandlerLive {
         client: &project_client,
+        templates: TemplateClientLive {
+            client: golem_cloud_client::api::TemplateClientLive {
+                context: context.clone(),
+            },
+        },
+        workers: WorkerClientLive {
+            client: golem_cloud_client::api::WorkerClientLive {
+                context: context.clone(),
+            },
+            context: context.clone(),
+            allow_insecure,
+        },
+        grants: ProjectGrantClientLive {
+            client: golem_cloud_client::api::ProjectGrantClientLive {
+                context: context.clone(),
+            },
+        },
+        policies: ProjectPolicyClientLive {
+            client: golem_cloud_client::api::ProjectPolicyClientLive {
+                context: context.clone(),
+            },
+        },
+        gateway_url: gateway_url.clone(),
+        http_client: client.clone(),
+        config_dir: config_dir.clone(),
+        profile: profile_name.clone(),
     };
     let template_client = TemplateClientLive {
         client: golem_cloud_client::api::TemplateClientLive {
@@ -321,6 +533,19 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
     let template_srv = TemplateHandlerLive {
         client: template_client,
         projects: &project_client,
+        workers: WorkerClientLive {
+            client: golem_cloud_client::api::WorkerClientLive {
+                context: context.clone(),
+            },
+            context: context.clone(),
+            allow_insecure,
+        },
+        accounts: AccountClientLive {
+            client: golem_cloud_client::api::AccountClientLive {
+                context: context.clone(),
+            },
+        },
+        account_id: auth.account_id(),
     };
     let project_policy_client = ProjectPolicyClientLive {
         client: golem_cloud_client::api::ProjectPolicyClientLive {
@@ -329,6 +554,12 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
     };
     let project_policy_srv = ProjectPolicyHandlerLive {
         client: project_policy_client,
+        projects: &project_client,
+        grants: ProjectGrantClientLive {
+            client: golem_cloud_client::api::ProjectGrantClientLive {
+                context: context.clone(),
+            },
+        },
     };
     let project_grant_client = ProjectGrantClientLive {
         client: golem_cloud_client::api::ProjectGrantClientLive {
@@ -350,14 +581,41 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
         client: worker_client,
         templates: &template_srv,
     };
+    let manifest_srv = ManifestHandlerLive {
+        templates: TemplateClientLive {
+            client: golem_cloud_client::api::TemplateClientLive {
+                context: context.clone(),
+            },
+        },
+        projects: &project_client,
+        workers: WorkerClientLive {
+            client: golem_cloud_client::api::WorkerClientLive {
+                context: context.clone(),
+            },
+            context: context.clone(),
+            allow_insecure,
+        },
+        grants: ProjectGrantClientLive {
+            client: golem_cloud_client::api::ProjectGrantClientLive {
+                context: context.clone(),
+            },
+        },
+        policies: ProjectPolicyClientLive {
+            client: golem_cloud_client::api::ProjectPolicyClientLive {
+                context: context.clone(),
+            },
+        },
+        gateway_url: gateway_url.clone(),
+        client: client.clone(),
+    };
     let gateway_srv = GatewayHandlerLive {
         base_url: gateway_url.clone(),
         client,
         projects: &project_client,
     };
 
-    let res = match cmd.command {
-        Command::Template { subcommand } => template_srv.handle(subcommand).await,
+    let res = match command {
+        Command::Template { subcommand } => template_srv.handle(&output, subcommand).await,
         Command::Worker { subcommand } => worker_srv.handle(subcommand).await,
         Command::Account {
             account_id,
@@ -373,6 +631,7 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
             recipient_account_id,
             project_policy_id,
             project_actions,
+            project_policy_name,
         } => {
             project_grant_srv
                 .handle(
@@ -380,6 +639,7 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
                     recipient_account_id,
                     project_policy_id,
                     project_actions,
+                    project_policy_name,
                 )
                 .await
         }
@@ -392,38 +652,90 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
         Command::ListExamples { min_tier, language } => {
             examples::process_list_examples(min_tier, language)
         }
-        Command::Gateway { subcommand } => gateway_srv.handle(cmd.format, &auth, subcommand).await,
+        Command::Gateway { subcommand } => gateway_srv.handle(format, &auth, subcommand).await,
+        Command::Manifest { subcommand } => manifest_srv.handle(&output, &auth, subcommand).await,
+        Command::Profile { .. } | Command::Auth { .. } => {
+            unreachable!("Handled before authentication")
+        }
         #[cfg(feature = "stubgen")]
         Command::Stubgen { subcommand } => match subcommand {
             golem_wasm_rpc_stubgen::Command::Generate(args) => {
                 golem_wasm_rpc_stubgen::generate(args)
-                    .map_err(|err| GolemError(format!("{err}")))
+                    .map_err(|err| GolemError::new(format!("{err}")))
                     .map(|_| GolemResult::Ok(Box::new("Done")))
             }
             golem_wasm_rpc_stubgen::Command::Build(args) => golem_wasm_rpc_stubgen::build(args)
                 .await
-                .map_err(|err| GolemError(format!("{err}")))
+                .map_err(|err| GolemError::new(format!("{err}")))
                 .map(|_| GolemResult::Ok(Box::new("Done"))),
             golem_wasm_rpc_stubgen::Command::AddStubDependency(args) => {
                 golem_wasm_rpc_stubgen::add_stub_dependency(args)
-                    .map_err(|err| GolemError(format!("{err}")))
+                    .map_err(|err| GolemError::new(format!("{err}")))
                     .map(|_| GolemResult::Ok(Box::new("Done")))
             }
             golem_wasm_rpc_stubgen::Command::Compose(args) => golem_wasm_rpc_stubgen::compose(args)
-                .map_err(|err| GolemError(format!("{err}")))
+                .map_err(|err| GolemError::new(format!("{err}")))
                 .map(|_| GolemResult::Ok(Box::new("Done"))),
             golem_wasm_rpc_stubgen::Command::InitializeWorkspace(args) => {
                 golem_wasm_rpc_stubgen::initialize_workspace(args, "golem-cloud-cli", &["stubgen"])
-                    .map_err(|err| GolemError(format!("{err}")))
+                    .map_err(|err| GolemError::new(format!("{err}")))
                     .map(|_| GolemResult::Ok(Box::new("Done")))
             }
         },
     };
 
+    // A stored token can be revoked or expire while in use, the services hold on to it though
+    let res = match res {
+        Err(err) if err.is_unauthorized() && manual_token.is_none() => {
+            let reason = format!("The stored token was rejected ({err})");
+            let auth = auth_srv.reauthenticate(auth_dir, &reason).await?;
+
+            Err(GolemError::new(format!(
+                "Logged in again as account {}, run the command again",
+                auth.account_id()
+            )))
+        }
+        res => res,
+    };
+
+    print_result(res, &output)
+}
+
+/// How results are printed, next to `--format`.
+#[derive(Args, Debug)]
+struct OutputArgs {
+    /// Columns shown by the table format, such as `projectId,projectData.name`
+    #[arg(long, global = true, value_delimiter = ',')]
+    columns: Vec<String>,
+
+    /// Prints only part of the result, such as `.[].templateId`, in a subset of jq or JSONPath
+    #[arg(long, global = true)]
+    query: Option<Query>,
+
+    /// Prints strings without quotes, and arrays of them one per line
+    #[arg(long, global = true)]
+    output_raw: bool,
+}
+
+impl OutputArgs {
+    fn output(&self, format: Format) -> Output {
+        Output {
+            format,
+            columns: self.columns.clone(),
+            query: self.query.clone(),
+            raw: self.output_raw,
+        }
+    }
+}
+
+fn print_result(
+    res: Result<GolemResult, GolemError>,
+    output: &Output,
+) -> Result<(), Box<dyn std::error::Error>> {
     match res {
         Ok(res) => match res {
             GolemResult::Ok(r) => {
-                r.println(&cmd.format);
+                r.println(output)?;
 
                 Ok(())
             }
@@ -432,10 +744,11 @@ async fn async_main(cmd: GolemCommand) -> Result<(), Box<dyn std::error::Error>>
 
                 Ok(())
             }
-            GolemResult::Json(json) => match &cmd.format {
-                Format::Json => Ok(println!("{}", serde_json::to_string_pretty(&json).unwrap())),
-                Format::Yaml => Ok(println!("{}", serde_yaml::to_string(&json).unwrap())),
-            },
+            GolemResult::Json(json) => {
+                output.print(json, &[])?;
+
+                Ok(())
+            }
         },
         Err(err) => Err(Box::new(err)),
     }
@@ -0,0 +1,197 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+pub mod plan;
+pub mod spec;
+
+use std::path::PathBuf;
+
+use async_trait::async_trait;
+use clap::Subcommand;
+use golem_gateway_client::{Context, Security};
+
+use crate::clients::gateway::definition::DefinitionClientLive;
+use crate::clients::gateway::deployment::DeploymentClientLive;
+use crate::clients::policy::ProjectPolicyClient;
+use crate::clients::project::ProjectClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::clients::template::TemplateClient;
+use crate::clients::worker::WorkerClient;
+use crate::clients::CloudAuthentication;
+use crate::confirm;
+use crate::manifest::plan::Planner;
+use crate::manifest::spec::Manifest;
+use crate::model::{GolemError, GolemResult, Output, PrintRes, ProjectId, ProjectRef};
+
+#[derive(Subcommand, Debug)]
+#[command()]
+pub enum ManifestSubcommand {
+    /// Shows the changes needed to bring a project in line with a manifest
+    #[command()]
+    Plan {
+        /// The project to compare, overrides the project named in the manifest
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The manifest describing the desired state of the project
+        #[arg(short, long, default_value = "golem.yaml", value_hint = clap::ValueHint::FilePath)]
+        manifest: PathBuf,
+    },
+
+    /// Applies the changes needed to bring a project in line with a manifest
+    #[command()]
+    Apply {
+        /// The project to update, overrides the project named in the manifest
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The manifest describing the desired state of the project
+        #[arg(short, long, default_value = "golem.yaml", value_hint = clap::ValueHint::FilePath)]
+        manifest: PathBuf,
+
+        /// Apply the changes without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[async_trait]
+pub trait ManifestHandler {
+    async fn handle(
+        &self,
+        output: &Output,
+        auth: &CloudAuthentication,
+        subcommand: ManifestSubcommand,
+    ) -> Result<GolemResult, GolemError>;
+}
+
+pub struct ManifestHandlerLive<
+    'p,
+    T: TemplateClient + Sync + Send,
+    P: ProjectClient + Sync + Send,
+    W: WorkerClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+> {
+    pub templates: T,
+    pub projects: &'p P,
+    pub workers: W,
+    pub grants: G,
+    pub policies: Po,
+    pub gateway_url: reqwest::Url,
+    pub client: reqwest::Client,
+}
+
+impl<
+        'p,
+        T: TemplateClient + Sync + Send,
+        P: ProjectClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        G: ProjectGrantClient + Sync + Send,
+        Po: ProjectPolicyClient + Sync + Send,
+    > ManifestHandlerLive<'p, T, P, W, G, Po>
+{
+    async fn load(
+        &self,
+        project_ref: ProjectRef,
+        path: PathBuf,
+    ) -> Result<(ProjectId, Manifest), GolemError> {
+        let manifest = Manifest::load(&path)?;
+
+        let project_ref = match (project_ref, &manifest.project) {
+            (ProjectRef::Default, Some(name)) => ProjectRef::Name(name.clone()),
+            (project_ref, _) => project_ref,
+        };
+        let project_id = self.projects.resolve_id_or_default(project_ref).await?;
+
+        Ok((project_id, manifest))
+    }
+}
+
+#[async_trait]
+impl<
+        'p,
+        T: TemplateClient + Sync + Send,
+        P: ProjectClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        G: ProjectGrantClient + Sync + Send,
+        Po: ProjectPolicyClient + Sync + Send,
+    > ManifestHandler for ManifestHandlerLive<'p, T, P, W, G, Po>
+{
+    async fn handle(
+        &self,
+        output: &Output,
+        auth: &CloudAuthentication,
+        subcommand: ManifestSubcommand,
+    ) -> Result<GolemResult, GolemError> {
+        let context = Context {
+            base_url: self.gateway_url.clone(),
+            client: self.client.clone(),
+            security_token: Security::Bearer(auth.0.secret.value.to_string()),
+        };
+        let definitions = DefinitionClientLive {
+            client: golem_gateway_client::api::ApiDefinitionClientLive {
+                context: context.clone(),
+            },
+        };
+        let deployments = DeploymentClientLive {
+            client: golem_gateway_client::api::ApiDeploymentClientLive {
+                context: context.clone(),
+            },
+        };
+
+        let planner = Planner {
+            templates: &self.templates,
+            workers: &self.workers,
+            grants: &self.grants,
+            policies: &self.policies,
+            definitions: &definitions,
+            deployments: &deployments,
+        };
+
+        match subcommand {
+            ManifestSubcommand::Plan {
+                project_ref,
+                manifest,
+            } => {
+                let (project_id, manifest) = self.load(project_ref, manifest).await?;
+                let plan = planner.plan(project_id, &manifest).await?;
+
+                Ok(GolemResult::Ok(Box::new(plan.view())))
+            }
+            ManifestSubcommand::Apply {
+                project_ref,
+                manifest,
+                yes,
+            } => {
+                let (project_id, manifest) = self.load(project_ref, manifest).await?;
+                let plan = planner.plan(project_id, &manifest).await?;
+
+                if plan.is_empty() {
+                    return Ok(GolemResult::Str("No changes".to_string()));
+                }
+
+                plan.view().println(output)?;
+
+                if !yes && !confirm("Apply these changes?")? {
+                    return Ok(GolemResult::Str("Apply cancelled".to_string()));
+                }
+
+                let applied = planner.apply(plan).await?;
+
+                Ok(GolemResult::Str(format!("Applied {applied} changes")))
+            }
+        }
+    }
+}
@@ -0,0 +1,1403 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use std::path::PathBuf;
+
+use golem_cloud_client::model::{ProjectGrant, ProjectPolicy};
+use golem_gateway_client::model::{ApiDefinition, ApiDeployment, ApiSite};
+use itertools::Itertools;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::clients::action_cli_to_api;
+use crate::clients::gateway::definition::DefinitionClient;
+use crate::clients::gateway::deployment::DeploymentClient;
+use crate::clients::policy::ProjectPolicyClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::clients::template::TemplateClient;
+use crate::clients::worker::WorkerClient;
+use crate::manifest::spec::{Manifest, PolicySpec};
+use crate::model::{
+    AccountId, GolemError, PathBufOrStdin, ProjectGrantId, ProjectId, ProjectPolicyId,
+    RawTemplateId, TemplateName, WorkerName,
+};
+use crate::table::TableColumns;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Create,
+    Update,
+    Replace,
+    Delete,
+}
+
+impl Display for ChangeKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ChangeKind::Create => "create",
+            ChangeKind::Update => "update",
+            ChangeKind::Replace => "replace",
+            ChangeKind::Delete => "delete",
+        };
+
+        Display::fmt(s, f)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub change: ChangeKind,
+    pub resource: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+impl Display for Change {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.change, self.resource)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlanView {
+    pub project_id: Uuid,
+    pub changes: Vec<Change>,
+}
+
+impl TableColumns for PlanView {}
+
+enum Operation {
+    CreateTemplate {
+        name: String,
+        file: PathBuf,
+    },
+    UpdateTemplate {
+        id: RawTemplateId,
+        file: PathBuf,
+    },
+    CreateWorker {
+        name: String,
+        template: String,
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+    },
+    ReplaceWorker {
+        template_id: RawTemplateId,
+        name: String,
+        args: Vec<String>,
+        env: BTreeMap<String, String>,
+    },
+    CreateGrant {
+        account_id: String,
+        policy: PolicySpec,
+    },
+    ReplaceGrant {
+        grant_id: ProjectGrantId,
+        account_id: String,
+        policy: PolicySpec,
+    },
+    DeleteGrant {
+        grant_id: ProjectGrantId,
+    },
+    PutDefinition(ApiDefinition),
+    DeleteDefinition {
+        id: String,
+    },
+    PutDeployment(ApiDeployment),
+    DeleteDeployment {
+        definition_id: String,
+        site: String,
+    },
+}
+
+pub struct Plan {
+    pub project_id: ProjectId,
+    steps: Vec<(Change, Operation)>,
+    /// Ids of the live templates, by name
+    templates: HashMap<String, RawTemplateId>,
+    /// Live policies matching a policy of the manifest, reused for new grants
+    policies: HashMap<String, ProjectPolicyId>,
+}
+
+impl Plan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Keeps only the creations, for merging into a project without touching its content.
+    pub fn only_creations(&mut self) {
+        self.steps
+            .retain(|(change, _)| change.change == ChangeKind::Create);
+    }
+
+    pub fn view(&self) -> PlanView {
+        PlanView {
+            project_id: self.project_id.0,
+            changes: self.steps.iter().map(|(c, _)| c.clone()).collect(),
+        }
+    }
+
+    fn push(
+        &mut self,
+        change: ChangeKind,
+        resource: String,
+        detail: Option<String>,
+        operation: Operation,
+    ) {
+        self.steps.push((
+            Change {
+                change,
+                resource,
+                detail,
+            },
+            operation,
+        ))
+    }
+}
+
+fn same_actions(spec: &PolicySpec, policy: &ProjectPolicy) -> bool {
+    let desired: Vec<golem_cloud_client::model::ProjectAction> = spec
+        .actions
+        .iter()
+        .copied()
+        .map(action_cli_to_api)
+        .collect();
+    let live = &policy.project_actions.actions;
+
+    desired.iter().all(|a| live.contains(a)) && live.iter().all(|a| desired.contains(a))
+}
+
+fn parse_template_id(id: &str) -> Result<RawTemplateId, GolemError> {
+    Uuid::parse_str(id)
+        .map(RawTemplateId)
+        .map_err(|err| GolemError::new(format!("Failed to parse template id: {err}")))
+}
+
+fn site_name(site: &ApiSite) -> String {
+    format!("{}.{}", site.subdomain, site.host)
+}
+
+/// Computes and executes the changes bringing a project in line with a manifest.
+pub struct Planner<'a, T, W, G, Po, D, De> {
+    pub templates: &'a T,
+    pub workers: &'a W,
+    pub grants: &'a G,
+    pub policies: &'a Po,
+    pub definitions: &'a D,
+    pub deployments: &'a De,
+}
+
+impl<'a, T, W, G, Po, D, De> Planner<'a, T, W, G, Po, D, De>
+where
+    T: TemplateClient + Sync + Send,
+    W: WorkerClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+    D: DefinitionClient + Sync + Send,
+    De: DeploymentClient + Sync + Send,
+{
+    pub async fn plan(
+        &self,
+        project_id: ProjectId,
+        manifest: &Manifest,
+    ) -> Result<Plan, GolemError> {
+        let mut plan = Plan {
+            project_id,
+            steps: Vec::new(),
+            templates: HashMap::new(),
+            policies: HashMap::new(),
+        };
+
+        self.plan_templates(&mut plan, manifest).await?;
+        self.plan_workers(&mut plan, manifest).await?;
+        self.plan_grants(&mut plan, manifest).await?;
+        self.plan_gateway(&mut plan, manifest).await?;
+
+        Ok(plan)
+    }
+
+    async fn plan_templates(&self, plan: &mut Plan, manifest: &Manifest) -> Result<(), GolemError> {
+        let live = self
+            .templates
+            .find(Some(plan.project_id.clone()), None)
+            .await?;
+
+        let mut latest = HashMap::new();
+        for (name, versions) in live
+            .into_iter()
+            .into_group_map_by(|t| t.template_name.clone())
+        {
+            if versions.iter().map(|t| &t.template_id).unique().count() > 1 {
+                let referenced = manifest.templates.iter().any(|t| t.name == name)
+                    || manifest.workers.iter().any(|w| w.template == name);
+
+                if referenced {
+                    return Err(GolemError::new(format!(
+                        "Multiple templates found for name {name}, the manifest can't refer to it"
+                    )));
+                }
+
+                continue;
+            }
+
+            let view = versions
+                .into_iter()
+                .max_by_key(|t| t.template_version)
+                .unwrap();
+            plan.templates
+                .insert(name.clone(), parse_template_id(&view.template_id)?);
+            latest.insert(name, view);
+        }
+
+        for spec in &manifest.templates {
+            let local = std::fs::read(&spec.file).map_err(|e| {
+                GolemError::new(format!("Can't read template file {:?}: {e}", spec.file))
+            })?;
+            let resource = format!("template {}", spec.name);
+
+            match latest.get(&spec.name) {
+                None => plan.push(
+                    ChangeKind::Create,
+                    resource,
+                    None,
+                    Operation::CreateTemplate {
+                        name: spec.name.clone(),
+                        file: spec.file.clone(),
+                    },
+                ),
+                Some(view) => {
+                    let id = parse_template_id(&view.template_id)?;
+
+                    // Sizes are known without downloading, so most changes are caught cheaply
+                    let changed = view.template_size as usize != local.len()
+                        || self
+                            .templates
+                            .download(&id, Some(view.template_version))
+                            .await?
+                            != local;
+
+                    if changed {
+                        plan.push(
+                            ChangeKind::Update,
+                            resource,
+                            Some(format!(
+                                "version {} differs from {}",
+                                view.template_version,
+                                spec.file.display()
+                            )),
+                            Operation::UpdateTemplate {
+                                id,
+                                file: spec.file.clone(),
+                            },
+                        )
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn plan_workers(&self, plan: &mut Plan, manifest: &Manifest) -> Result<(), GolemError> {
+        for spec in &manifest.workers {
+            let resource = format!("worker {}/{}", spec.template, spec.name);
+
+            match plan.templates.get(&spec.template).cloned() {
+                None => {
+                    if !manifest.templates.iter().any(|t| t.name == spec.template) {
+                        return Err(GolemError::new(format!(
+                            "Worker {} refers to unknown template {}",
+                            spec.name, spec.template
+                        )));
+                    }
+
+                    plan.push(
+                        ChangeKind::Create,
+                        resource,
+                        None,
+                        Operation::CreateWorker {
+                            name: spec.name.clone(),
+                            template: spec.template.clone(),
+                            args: spec.args.clone(),
+                            env: spec.env.clone(),
+                        },
+                    )
+                }
+                Some(template_id) => {
+                    let live = self
+                        .workers
+                        .find_metadata(WorkerName(spec.name.clone()), template_id.clone())
+                        .await?;
+
+                    match live {
+                        None => plan.push(
+                            ChangeKind::Create,
+                            resource,
+                            None,
+                            Operation::CreateWorker {
+                                name: spec.name.clone(),
+                                template: spec.template.clone(),
+                                args: spec.args.clone(),
+                                env: spec.env.clone(),
+                            },
+                        ),
+                        Some(metadata) => {
+                            let env: BTreeMap<String, String> = metadata.env.into_iter().collect();
+
+                            if metadata.args != spec.args || env != spec.env {
+                                plan.push(
+                                    ChangeKind::Replace,
+                                    resource,
+                                    Some(
+                                        "args or env changed, the worker's state is lost"
+                                            .to_string(),
+                                    ),
+                                    Operation::ReplaceWorker {
+                                        template_id,
+                                        name: spec.name.clone(),
+                                        args: spec.args.clone(),
+                                        env: spec.env.clone(),
+                                    },
+                                )
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn plan_grants(&self, plan: &mut Plan, manifest: &Manifest) -> Result<(), GolemError> {
+        let Some(grants) = &manifest.grants else {
+            return Ok(());
+        };
+
+        // Policies can't be listed, only the ones referenced by grants are known
+        let mut live: Vec<(ProjectGrant, ProjectPolicy)> = Vec::new();
+        let mut policies: HashMap<Uuid, ProjectPolicy> = HashMap::new();
+        for grant in self.grants.find(plan.project_id.clone()).await? {
+            let policy_id = grant.data.project_policy_id;
+            let policy = match policies.get(&policy_id) {
+                Some(policy) => policy.clone(),
+                None => {
+                    let policy = self.policies.get(ProjectPolicyId(policy_id)).await?;
+                    policies.insert(policy_id, policy.clone());
+                    policy
+                }
+            };
+            live.push((grant, policy));
+        }
+
+        for policy in policies.values() {
+            if let Some(spec) = manifest.policy(&policy.name) {
+                if same_actions(spec, policy) {
+                    plan.policies
+                        .insert(spec.name.clone(), ProjectPolicyId(policy.id));
+                }
+            }
+        }
+
+        for grant in grants {
+            // Validated when loading the manifest
+            let spec = manifest.policy(&grant.policy).unwrap();
+            let resource = format!("grant {} to account {}", spec.name, grant.account_id);
+
+            let same_grant = |(g, p): &(ProjectGrant, ProjectPolicy)| {
+                g.data.grantee_account_id == grant.account_id && p.name == spec.name
+            };
+
+            if let Some(i) = live
+                .iter()
+                .position(|gp| same_grant(gp) && same_actions(spec, &gp.1))
+            {
+                live.remove(i);
+            } else if let Some(i) = live.iter().position(same_grant) {
+                let (outdated, _) = live.remove(i);
+                plan.push(
+                    ChangeKind::Replace,
+                    resource,
+                    Some("policy actions changed".to_string()),
+                    Operation::ReplaceGrant {
+                        grant_id: ProjectGrantId(outdated.id),
+                        account_id: grant.account_id.clone(),
+                        policy: spec.clone(),
+                    },
+                )
+            } else {
+                plan.push(
+                    ChangeKind::Create,
+                    resource,
+                    None,
+                    Operation::CreateGrant {
+                        account_id: grant.account_id.clone(),
+                        policy: spec.clone(),
+                    },
+                )
+            }
+        }
+
+        for (grant, policy) in live {
+            plan.push(
+                ChangeKind::Delete,
+                format!(
+                    "grant {} to account {}",
+                    policy.name, grant.data.grantee_account_id
+                ),
+                None,
+                Operation::DeleteGrant {
+                    grant_id: ProjectGrantId(grant.id),
+                },
+            )
+        }
+
+        Ok(())
+    }
+
+    async fn plan_gateway(&self, plan: &mut Plan, manifest: &Manifest) -> Result<(), GolemError> {
+        if manifest.api_definitions.is_none() && manifest.deployments.is_none() {
+            return Ok(());
+        }
+
+        let project_id = plan.project_id.clone();
+        let live_definitions = self.definitions.get(project_id.clone(), None).await?;
+
+        // Deletions run last, deployments before the definitions they use
+        let mut deployment_deletions = Vec::new();
+        let mut definition_deletions = Vec::new();
+
+        if let Some(definitions) = &manifest.api_definitions {
+            for spec in definitions {
+                let desired = ApiDefinition {
+                    id: spec.id.clone(),
+                    version: spec.version.clone(),
+                    project_id: project_id.0,
+                    routes: spec.routes.clone(),
+                };
+                let resource = format!("API definition {}", spec.id);
+
+                match live_definitions.iter().find(|d| d.id == spec.id) {
+                    None => plan.push(
+                        ChangeKind::Create,
+                        resource,
+                        None,
+                        Operation::PutDefinition(desired),
+                    ),
+                    Some(live) if *live != desired => plan.push(
+                        ChangeKind::Update,
+                        resource,
+                        None,
+                        Operation::PutDefinition(desired),
+                    ),
+                    Some(_) => {}
+                }
+            }
+
+            for live in &live_definitions {
+                if !definitions.iter().any(|d| d.id == live.id) {
+                    definition_deletions.push((
+                        Change {
+                            change: ChangeKind::Delete,
+                            resource: format!("API definition {}", live.id),
+                            detail: None,
+                        },
+                        Operation::DeleteDefinition {
+                            id: live.id.clone(),
+                        },
+                    ))
+                }
+            }
+        }
+
+        if let Some(deployments) = &manifest.deployments {
+            let mut live = Vec::new();
+            for definition in &live_definitions {
+                live.extend(
+                    self.deployments
+                        .get(project_id.clone(), &definition.id)
+                        .await?,
+                );
+            }
+
+            let desired: Vec<ApiDeployment> = deployments
+                .iter()
+                .map(|d| ApiDeployment {
+                    api_definition_id: d.definition_id.clone(),
+                    project_id: project_id.0,
+                    site: ApiSite {
+                        host: d.host.clone(),
+                        subdomain: d.subdomain.clone(),
+                    },
+                })
+                .collect();
+
+            for deployment in &live {
+                if !desired.contains(deployment) {
+                    deployment_deletions.push((
+                        Change {
+                            change: ChangeKind::Delete,
+                            resource: format!(
+                                "deployment of {} on {}",
+                                deployment.api_definition_id,
+                                site_name(&deployment.site)
+                            ),
+                            detail: None,
+                        },
+                        Operation::DeleteDeployment {
+                            definition_id: deployment.api_definition_id.clone(),
+                            site: site_name(&deployment.site),
+                        },
+                    ))
+                }
+            }
+
+            for deployment in desired {
+                if !live.contains(&deployment) {
+                    plan.push(
+                        ChangeKind::Create,
+                        format!(
+                            "deployment of {} on {}",
+                            deployment.api_definition_id,
+                            site_name(&deployment.site)
+                        ),
+                        None,
+                        Operation::PutDeployment(deployment),
+                    )
+                }
+            }
+        }
+
+        plan.steps.extend(deployment_deletions);
+        plan.steps.extend(definition_deletions);
+
+        Ok(())
+    }
+
+    /// Executes a plan, stopping at the first failure.
+    pub async fn apply(&self, plan: Plan) -> Result<usize, GolemError> {
+        let Plan {
+            project_id,
+            steps,
+            mut templates,
+            mut policies,
+        } = plan;
+        let total = steps.len();
+
+        for (applied, (change, operation)) in steps.into_iter().enumerate() {
+            self.execute(&project_id, operation, &mut templates, &mut policies)
+                .await
+                .map_err(|GolemError { message: err, .. }| {
+                    GolemError::new(format!(
+                        "Failed to {change}: {err}\n{applied} of {total} changes were applied"
+                    ))
+                })?;
+        }
+
+        Ok(total)
+    }
+
+    /// Executes a plan, carrying on past failures, and returns the failed changes with their
+    /// errors next to the number of applied changes.
+    pub async fn apply_all(&self, plan: Plan) -> (usize, Vec<String>) {
+        let Plan {
+            project_id,
+            steps,
+            mut templates,
+            mut policies,
+        } = plan;
+        let mut applied = 0;
+        let mut failed = Vec::new();
+
+        for (change, operation) in steps {
+            match self
+                .execute(&project_id, operation, &mut templates, &mut policies)
+                .await
+            {
+                Ok(()) => applied += 1,
+                Err(GolemError { message: err, .. }) => {
+                    failed.push(format!("Failed to {change}: {err}"))
+                }
+            }
+        }
+
+        (applied, failed)
+    }
+
+    async fn execute(
+        &self,
+        project_id: &ProjectId,
+        operation: Operation,
+        templates: &mut HashMap<String, RawTemplateId>,
+        policies: &mut HashMap<String, ProjectPolicyId>,
+    ) -> Result<(), GolemError> {
+        match operation {
+            Operation::CreateTemplate { name, file } => {
+                let template = self
+                    .templates
+                    .add(
+                        Some(project_id.clone()),
+                        TemplateName(name.clone()),
+                        PathBufOrStdin::Path(file),
+                    )
+                    .await?;
+                templates.insert(name, parse_template_id(&template.template_id)?);
+            }
+            Operation::UpdateTemplate { id, file } => {
+                self.templates
+                    .update(id, PathBufOrStdin::Path(file))
+                    .await?;
+            }
+            Operation::CreateWorker {
+                name,
+                template,
+                args,
+                env,
+            } => {
+                let template_id = templates.get(&template).cloned().ok_or_else(|| {
+                    GolemError::new(format!("Template {template} was not created"))
+                })?;
+                self.workers
+                    .new_worker(
+                        WorkerName(name),
+                        template_id,
+                        args,
+                        env.into_iter().collect(),
+                    )
+                    .await?;
+            }
+            Operation::ReplaceWorker {
+                template_id,
+                name,
+                args,
+                env,
+            } => {
+                self.workers
+                    .delete(WorkerName(name.clone()), template_id.clone())
+                    .await?;
+                self.workers
+                    .new_worker(
+                        WorkerName(name),
+                        template_id,
+                        args,
+                        env.into_iter().collect(),
+                    )
+                    .await?;
+            }
+            Operation::CreateGrant { account_id, policy } => {
+                self.create_grant(project_id, account_id, &policy, policies)
+                    .await?;
+            }
+            Operation::ReplaceGrant {
+                grant_id,
+                account_id,
+                policy,
+            } => {
+                // The new grant is created first, so the grantee never loses access
+                self.create_grant(project_id, account_id, &policy, policies)
+                    .await?;
+                self.grants.delete(project_id.clone(), grant_id).await?;
+            }
+            Operation::DeleteGrant { grant_id } => {
+                self.grants.delete(project_id.clone(), grant_id).await?;
+            }
+            Operation::PutDefinition(definition) => {
+                self.definitions.update(definition).await?;
+            }
+            Operation::DeleteDefinition { id } => {
+                self.definitions.delete(project_id.clone(), &id).await?;
+            }
+            Operation::PutDeployment(deployment) => {
+                self.deployments.update(deployment).await?;
+            }
+            Operation::DeleteDeployment {
+                definition_id,
+                site,
+            } => {
+                self.deployments
+                    .delete(project_id.clone(), &definition_id, &site)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Policies are immutable, so a policy is created once per apply and shared by its grants.
+    async fn create_grant(
+        &self,
+        project_id: &ProjectId,
+        account_id: String,
+        policy: &PolicySpec,
+        policies: &mut HashMap<String, ProjectPolicyId>,
+    ) -> Result<(), GolemError> {
+        let policy_id = match policies.get(&policy.name) {
+            Some(id) => id.clone(),
+            None => {
+                let created = self
+                    .policies
+                    .create(policy.name.clone(), policy.actions.clone())
+                    .await?;
+                let id = ProjectPolicyId(created.id);
+                policies.insert(policy.name.clone(), id.clone());
+                id
+            }
+        };
+
+        self.grants
+            .create(project_id.clone(), AccountId::new(account_id), policy_id)
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+    use std::sync::Mutex;
+
+    use async_trait::async_trait;
+    use golem_cloud_client::model::{
+        InvokeParameters, InvokeResult, ProjectActions, ProjectGrant, ProjectGrantData,
+        ProjectPolicy, Template, VersionedWorkerId, WorkerMetadata,
+    };
+    use golem_gateway_client::model::{ApiDefinition, ApiDeployment, ApiSite};
+    use uuid::Uuid;
+
+    use crate::clients::action_cli_to_api;
+    use crate::clients::gateway::definition::DefinitionClient;
+    use crate::clients::gateway::deployment::DeploymentClient;
+    use crate::clients::policy::ProjectPolicyClient;
+    use crate::clients::project_grant::ProjectGrantClient;
+    use crate::clients::template::{TemplateClient, TemplateView};
+    use crate::clients::worker::WorkerClient;
+    use crate::manifest::plan::{ChangeKind, Planner};
+    use crate::manifest::spec::{
+        DefinitionSpec, DeploymentSpec, GrantSpec, Manifest, PolicySpec, TemplateSpec,
+    };
+    use crate::model::{
+        AccountId, GolemError, InvocationKey, PathBufOrStdin, ProjectAction, ProjectGrantId,
+        ProjectId, ProjectPolicyId, RawTemplateId, TemplateName, WorkerName,
+    };
+
+    /// The live state of a project, as seen by every client the planner uses.
+    #[derive(Default)]
+    struct FakeProject {
+        templates: Vec<TemplateView>,
+        template_bytes: Vec<u8>,
+        grants: Vec<ProjectGrant>,
+        policies: Vec<ProjectPolicy>,
+        definitions: Vec<ApiDefinition>,
+        deployments: Vec<ApiDeployment>,
+        created_policies: Mutex<Vec<String>>,
+        created_grants: Mutex<Vec<(String, Uuid)>>,
+        /// Grant creations and deletions, in order
+        grant_changes: Mutex<Vec<String>>,
+    }
+
+    impl FakeProject {
+        fn planner(&self) -> Planner<'_, Self, Self, Self, Self, Self, Self> {
+            Planner {
+                templates: self,
+                workers: self,
+                grants: self,
+                policies: self,
+                definitions: self,
+                deployments: self,
+            }
+        }
+
+        fn with_grant(mut self, account_id: &str, policy: &str, actions: &[ProjectAction]) -> Self {
+            let policy_id = match self.policies.iter().find(|p| p.name == policy) {
+                Some(p) => p.id,
+                None => {
+                    let id = Uuid::from_u128(rand::random());
+                    self.policies.push(ProjectPolicy {
+                        id,
+                        name: policy.to_string(),
+                        project_actions: ProjectActions {
+                            actions: actions.iter().copied().map(action_cli_to_api).collect(),
+                        },
+                    });
+                    id
+                }
+            };
+            self.grants.push(ProjectGrant {
+                id: Uuid::from_u128(rand::random()),
+                data: ProjectGrantData {
+                    grantee_account_id: account_id.to_string(),
+                    grantor_project_id: project().0,
+                    project_policy_id: policy_id,
+                },
+            });
+            self
+        }
+    }
+
+    #[async_trait]
+    impl TemplateClient for FakeProject {
+        async fn find(
+            &self,
+            _project_id: Option<ProjectId>,
+            _name: Option<TemplateName>,
+        ) -> Result<Vec<TemplateView>, GolemError> {
+            Ok(self.templates.clone())
+        }
+
+        async fn add(
+            &self,
+            _project_id: Option<ProjectId>,
+            _name: TemplateName,
+            _file: PathBufOrStdin,
+        ) -> Result<TemplateView, GolemError> {
+            unused()
+        }
+
+        async fn update(
+            &self,
+            _id: RawTemplateId,
+            _file: PathBufOrStdin,
+        ) -> Result<TemplateView, GolemError> {
+            unused()
+        }
+
+        async fn get_metadata(
+            &self,
+            _id: &RawTemplateId,
+            _version: i32,
+        ) -> Result<Template, GolemError> {
+            unused()
+        }
+
+        async fn get_latest_metadata(&self, _id: &RawTemplateId) -> Result<Template, GolemError> {
+            unused()
+        }
+
+        async fn get_versions(&self, _id: &RawTemplateId) -> Result<Vec<Template>, GolemError> {
+            unused()
+        }
+
+        async fn download(
+            &self,
+            _id: &RawTemplateId,
+            _version: Option<i32>,
+        ) -> Result<Vec<u8>, GolemError> {
+            Ok(self.template_bytes.clone())
+        }
+    }
+
+    #[async_trait]
+    impl WorkerClient for FakeProject {
+        async fn new_worker(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+            _args: Vec<String>,
+            _env: Vec<(String, String)>,
+        ) -> Result<VersionedWorkerId, GolemError> {
+            unused()
+        }
+
+        async fn get_invocation_key(
+            &self,
+            _name: &WorkerName,
+            _template_id: &RawTemplateId,
+        ) -> Result<InvocationKey, GolemError> {
+            unused()
+        }
+
+        async fn invoke_and_await(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+            _function: String,
+            _parameters: InvokeParameters,
+            _invocation_key: InvocationKey,
+            _use_stdio: bool,
+        ) -> Result<InvokeResult, GolemError> {
+            unused()
+        }
+
+        async fn invoke(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+            _function: String,
+            _parameters: InvokeParameters,
+        ) -> Result<(), GolemError> {
+            unused()
+        }
+
+        async fn interrupt(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<(), GolemError> {
+            unused()
+        }
+
+        async fn simulated_crash(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<(), GolemError> {
+            unused()
+        }
+
+        async fn delete(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<(), GolemError> {
+            unused()
+        }
+
+        async fn get_metadata(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<WorkerMetadata, GolemError> {
+            unused()
+        }
+
+        async fn find_metadata(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<Option<WorkerMetadata>, GolemError> {
+            Ok(None)
+        }
+
+        async fn connect(
+            &self,
+            _name: WorkerName,
+            _template_id: RawTemplateId,
+        ) -> Result<(), GolemError> {
+            unused()
+        }
+    }
+
+    #[async_trait]
+    impl ProjectGrantClient for FakeProject {
+        async fn create(
+            &self,
+            _project_id: ProjectId,
+            account_id: AccountId,
+            policy_id: ProjectPolicyId,
+        ) -> Result<ProjectGrant, GolemError> {
+            self.created_grants
+                .lock()
+                .unwrap()
+                .push((account_id.id.clone(), policy_id.0));
+            self.grant_changes
+                .lock()
+                .unwrap()
+                .push(format!("create {}", account_id.id));
+            Ok(ProjectGrant {
+                id: Uuid::from_u128(rand::random()),
+                data: ProjectGrantData {
+                    grantee_account_id: account_id.id,
+                    grantor_project_id: project().0,
+                    project_policy_id: policy_id.0,
+                },
+            })
+        }
+
+        async fn create_actions(
+            &self,
+            _project_id: ProjectId,
+            _account_id: AccountId,
+            _actions: Vec<ProjectAction>,
+            _policy_name: Option<String>,
+        ) -> Result<ProjectGrant, GolemError> {
+            unused()
+        }
+
+        async fn find(&self, _project_id: ProjectId) -> Result<Vec<ProjectGrant>, GolemError> {
+            Ok(self.grants.clone())
+        }
+
+        async fn delete(
+            &self,
+            _project_id: ProjectId,
+            grant_id: ProjectGrantId,
+        ) -> Result<(), GolemError> {
+            let grant = self.grants.iter().find(|g| g.id == grant_id.0).unwrap();
+            self.grant_changes
+                .lock()
+                .unwrap()
+                .push(format!("delete {}", grant.data.grantee_account_id));
+            Ok(())
+        }
+    }
+
+    #[async_trait]
+    impl ProjectPolicyClient for FakeProject {
+        async fn create(
+            &self,
+            name: String,
+            actions: Vec<ProjectAction>,
+        ) -> Result<ProjectPolicy, GolemError> {
+            self.created_policies.lock().unwrap().push(name.clone());
+            Ok(ProjectPolicy {
+                id: Uuid::from_u128(rand::random()),
+                name,
+                project_actions: ProjectActions {
+                    actions: actions.into_iter().map(action_cli_to_api).collect(),
+                },
+            })
+        }
+
+        async fn get(&self, policy_id: ProjectPolicyId) -> Result<ProjectPolicy, GolemError> {
+            Ok(self
+                .policies
+                .iter()
+                .find(|p| p.id == policy_id.0)
+                .cloned()
+                .unwrap())
+        }
+    }
+
+    #[async_trait]
+    impl DefinitionClient for FakeProject {
+        async fn get(
+            &self,
+            _project_id: ProjectId,
+            _api_definition_id: Option<&str>,
+        ) -> Result<Vec<ApiDefinition>, GolemError> {
+            Ok(self.definitions.clone())
+        }
+
+        async fn update(
+            &self,
+            _api_definition: ApiDefinition,
+        ) -> Result<ApiDefinition, GolemError> {
+            unused()
+        }
+
+        async fn delete(
+            &self,
+            _project_id: ProjectId,
+            _api_definition_id: &str,
+        ) -> Result<String, GolemError> {
+            unused()
+        }
+    }
+
+    #[async_trait]
+    impl DeploymentClient for FakeProject {
+        async fn get(
+            &self,
+            _project_id: ProjectId,
+            api_definition_id: &str,
+        ) -> Result<Vec<ApiDeployment>, GolemError> {
+            Ok(self
+                .deployments
+                .iter()
+                .filter(|d| d.api_definition_id == api_definition_id)
+                .cloned()
+                .collect())
+        }
+
+        async fn update(
+            &self,
+            _api_deployment: ApiDeployment,
+        ) -> Result<ApiDeployment, GolemError> {
+            unused()
+        }
+
+        async fn delete(
+            &self,
+            _project_id: ProjectId,
+            _api_definition_id: &str,
+            _site: &str,
+        ) -> Result<String, GolemError> {
+            unused()
+        }
+    }
+
+    /// Client calls the planner doesn't make, failing the test if it does.
+    fn unused<T>() -> Result<T, GolemError> {
+        Err(GolemError::new(
+            "Not expected to be called by the planner".to_string(),
+        ))
+    }
+
+    fn project() -> ProjectId {
+        ProjectId(Uuid::nil())
+    }
+
+    fn grant(account_id: &str, policy: &str) -> GrantSpec {
+        GrantSpec {
+            account_id: account_id.to_string(),
+            policy: policy.to_string(),
+        }
+    }
+
+    fn policy(name: &str, actions: &[ProjectAction]) -> PolicySpec {
+        PolicySpec {
+            name: name.to_string(),
+            actions: actions.to_vec(),
+        }
+    }
+
+    fn definition(id: &str, version: &str) -> ApiDefinition {
+        ApiDefinition {
+            id: id.to_string(),
+            version: version.to_string(),
+            project_id: project().0,
+            routes: Vec::new(),
+        }
+    }
+
+    fn deployment(definition_id: &str, subdomain: &str) -> ApiDeployment {
+        ApiDeployment {
+            api_definition_id: definition_id.to_string(),
+            project_id: project().0,
+            site: ApiSite {
+                host: "example.com".to_string(),
+                subdomain: subdomain.to_string(),
+            },
+        }
+    }
+
+    async fn changes(live: &FakeProject, manifest: &Manifest) -> Vec<(ChangeKind, String)> {
+        live.planner()
+            .plan(project(), manifest)
+            .await
+            .unwrap()
+            .view()
+            .changes
+            .into_iter()
+            .map(|c| (c.change, c.resource))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn unmanaged_sections_are_left_alone() {
+        let live = FakeProject {
+            definitions: vec![definition("api", "1")],
+            deployments: vec![deployment("api", "www")],
+            ..FakeProject::default()
+        }
+        .with_grant("alice", "viewer", &[ProjectAction::ViewTemplate]);
+
+        assert_eq!(changes(&live, &Manifest::default()).await, vec![]);
+    }
+
+    #[tokio::test]
+    async fn matching_grants_are_kept_and_others_deleted() {
+        let live = FakeProject::default()
+            .with_grant("alice", "viewer", &[ProjectAction::ViewTemplate])
+            .with_grant("bob", "viewer", &[ProjectAction::ViewTemplate]);
+        let manifest = Manifest {
+            policies: vec![policy("viewer", &[ProjectAction::ViewTemplate])],
+            grants: Some(vec![grant("alice", "viewer"), grant("carol", "viewer")]),
+            ..Manifest::default()
+        };
+
+        assert_eq!(
+            changes(&live, &manifest).await,
+            vec![
+                (
+                    ChangeKind::Create,
+                    "grant viewer to account carol".to_string()
+                ),
+                (
+                    ChangeKind::Delete,
+                    "grant viewer to account bob".to_string()
+                ),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn changed_policy_actions_replace_the_grant() {
+        let live =
+            FakeProject::default().with_grant("alice", "viewer", &[ProjectAction::ViewTemplate]);
+        let manifest = Manifest {
+            policies: vec![policy(
+                "viewer",
+                &[ProjectAction::ViewTemplate, ProjectAction::ViewWorker],
+            )],
+            grants: Some(vec![grant("alice", "viewer")]),
+            ..Manifest::default()
+        };
+
+        assert_eq!(
+            changes(&live, &manifest).await,
+            vec![(
+                ChangeKind::Replace,
+                "grant viewer to account alice".to_string()
+            )]
+        );
+    }
+
+    #[tokio::test]
+    async fn replaced_grants_are_created_before_the_old_one_is_deleted() {
+        let live =
+            FakeProject::default().with_grant("alice", "viewer", &[ProjectAction::ViewTemplate]);
+        let manifest = Manifest {
+            policies: vec![policy("viewer", &[ProjectAction::ViewWorker])],
+            grants: Some(vec![grant("alice", "viewer")]),
+            ..Manifest::default()
+        };
+
+        let planner = live.planner();
+        let plan = planner.plan(project(), &manifest).await.unwrap();
+        assert_eq!(planner.apply(plan).await.unwrap(), 1);
+
+        assert_eq!(
+            *live.grant_changes.lock().unwrap(),
+            vec!["create alice", "delete alice"]
+        );
+    }
+
+    #[tokio::test]
+    async fn new_grants_share_one_new_policy_and_reuse_matching_ones() {
+        let live =
+            FakeProject::default().with_grant("alice", "viewer", &[ProjectAction::ViewTemplate]);
+        let viewer = live.policies[0].id;
+        let manifest = Manifest {
+            policies: vec![
+                policy("viewer", &[ProjectAction::ViewTemplate]),
+                policy("editor", &[ProjectAction::UpdateTemplate]),
+            ],
+            grants: Some(vec![
+                grant("alice", "viewer"),
+                grant("bob", "viewer"),
+                grant("bob", "editor"),
+                grant("carol", "editor"),
+            ]),
+            ..Manifest::default()
+        };
+
+        let planner = live.planner();
+        let plan = planner.plan(project(), &manifest).await.unwrap();
+        assert_eq!(planner.apply(plan).await.unwrap(), 3);
+
+        assert_eq!(*live.created_policies.lock().unwrap(), vec!["editor"]);
+        let created = live.created_grants.lock().unwrap();
+        assert_eq!(created[0], ("bob".to_string(), viewer));
+        assert_eq!(created[1].1, created[2].1);
+        assert_ne!(created[1].1, viewer);
+    }
+
+    #[tokio::test]
+    async fn gateway_changes_delete_deployments_before_definitions() {
+        let live = FakeProject {
+            definitions: vec![
+                definition("kept", "1"),
+                definition("changed", "1"),
+                definition("removed", "1"),
+            ],
+            deployments: vec![deployment("kept", "www"), deployment("removed", "old")],
+            ..FakeProject::default()
+        };
+        let manifest = Manifest {
+            api_definitions: Some(vec![
+                DefinitionSpec {
+                    id: "kept".to_string(),
+                    version: "1".to_string(),
+                    routes: Vec::new(),
+                },
+                DefinitionSpec {
+                    id: "changed".to_string(),
+                    version: "2".to_string(),
+                    routes: Vec::new(),
+                },
+            ]),
+            deployments: Some(vec![
+                DeploymentSpec {
+                    definition_id: "kept".to_string(),
+                    host: "example.com".to_string(),
+                    subdomain: "www".to_string(),
+                },
+                DeploymentSpec {
+                    definition_id: "changed".to_string(),
+                    host: "example.com".to_string(),
+                    subdomain: "new".to_string(),
+                },
+            ]),
+            ..Manifest::default()
+        };
+
+        assert_eq!(
+            changes(&live, &manifest).await,
+            vec![
+                (ChangeKind::Update, "API definition changed".to_string()),
+                (
+                    ChangeKind::Create,
+                    "deployment of changed on new.example.com".to_string()
+                ),
+                (
+                    ChangeKind::Delete,
+                    "deployment of removed on old.example.com".to_string()
+                ),
+                (ChangeKind::Delete, "API definition removed".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn templates_are_updated_only_when_their_content_differs() {
+        let mut file = tempfile::NamedTempFile::new().unwrap();
+        file.write_all(b"wasm").unwrap();
+        let spec = |name: &str| TemplateSpec {
+            name: name.to_string(),
+            file: file.path().to_path_buf(),
+        };
+        let view = |name: &str| TemplateView {
+            template_id: Uuid::from_u128(rand::random()).to_string(),
+            template_version: 1,
+            template_name: name.to_string(),
+            template_size: 4,
+            exports: Vec::new(),
+        };
+        let live = FakeProject {
+            templates: vec![view("same"), view("changed")],
+            template_bytes: b"wasm".to_vec(),
+            ..FakeProject::default()
+        };
+        let same_size = FakeProject {
+            templates: vec![view("changed")],
+            template_bytes: b"WASM".to_vec(),
+            ..FakeProject::default()
+        };
+        let manifest = Manifest {
+            templates: vec![spec("same"), spec("new")],
+            ..Manifest::default()
+        };
+
+        assert_eq!(
+            changes(&live, &manifest).await,
+            vec![(ChangeKind::Create, "template new".to_string())]
+        );
+
+        let manifest = Manifest {
+            templates: vec![spec("changed")],
+            ..Manifest::default()
+        };
+        assert_eq!(
+            changes(&same_size, &manifest).await,
+            vec![(ChangeKind::Update, "template changed".to_string())]
+        );
+    }
+}
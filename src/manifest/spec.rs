@@ -0,0 +1,247 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use golem_gateway_client::model::Route;
+use serde::{Deserialize, Serialize};
+
+use crate::model::{GolemError, ProjectAction};
+
+/// Desired state of a project.
+///
+/// Sections that are left out entirely are not managed, so their live resources are never
+/// deleted. Templates and workers can't be listed or deleted through the API, so they are
+/// only ever created or updated.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct Manifest {
+    /// Name of the project, the default project is used when missing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(default)]
+    pub templates: Vec<TemplateSpec>,
+    #[serde(default)]
+    pub workers: Vec<WorkerSpec>,
+    #[serde(default)]
+    pub policies: Vec<PolicySpec>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub grants: Option<Vec<GrantSpec>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub api_definitions: Option<Vec<DefinitionSpec>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub deployments: Option<Vec<DeploymentSpec>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct TemplateSpec {
+    pub name: String,
+    /// The template WASM, relative to the manifest
+    pub file: PathBuf,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct WorkerSpec {
+    pub name: String,
+    /// Name of the template, either from the manifest or already in the project
+    pub template: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: BTreeMap<String, String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PolicySpec {
+    pub name: String,
+    pub actions: Vec<ProjectAction>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct GrantSpec {
+    pub account_id: String,
+    /// Name of a policy from the manifest
+    pub policy: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DefinitionSpec {
+    pub id: String,
+    pub version: String,
+    pub routes: Vec<Route>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct DeploymentSpec {
+    pub definition_id: String,
+    pub host: String,
+    pub subdomain: String,
+}
+
+impl Manifest {
+    /// Reads a YAML (or JSON) manifest, resolving template files relative to it.
+    pub fn load(path: &Path) -> Result<Manifest, GolemError> {
+        let file = File::open(path)
+            .map_err(|e| GolemError::new(format!("Failed to open manifest {path:?}: {e}")))?;
+        let mut manifest: Manifest = serde_yaml::from_reader(BufReader::new(file))
+            .map_err(|e| GolemError::new(format!("Failed to parse manifest {path:?}: {e}")))?;
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        for template in manifest.templates.iter_mut() {
+            template.file = base.join(&template.file);
+        }
+
+        manifest.validate()?;
+
+        Ok(manifest)
+    }
+
+    pub fn policy(&self, name: &str) -> Option<&PolicySpec> {
+        self.policies.iter().find(|p| p.name == name)
+    }
+
+    fn validate(&self) -> Result<(), GolemError> {
+        fn unique(kind: &str, names: impl Iterator<Item = String>) -> Result<(), GolemError> {
+            let mut seen = HashSet::new();
+            for name in names {
+                if seen.contains(&name) {
+                    return Err(GolemError::new(format!(
+                        "Duplicate {kind} {name} in manifest"
+                    )));
+                }
+                seen.insert(name);
+            }
+            Ok(())
+        }
+
+        unique("template", self.templates.iter().map(|t| t.name.clone()))?;
+        unique(
+            "worker",
+            self.workers
+                .iter()
+                .map(|w| format!("{}/{}", w.template, w.name)),
+        )?;
+        unique("policy", self.policies.iter().map(|p| p.name.clone()))?;
+        unique(
+            "API definition",
+            self.api_definitions.iter().flatten().map(|d| d.id.clone()),
+        )?;
+        // A site serves a single definition
+        unique(
+            "deployment site",
+            self.deployments
+                .iter()
+                .flatten()
+                .map(|d| format!("{}.{}", d.subdomain, d.host)),
+        )?;
+
+        for grant in self.grants.iter().flatten() {
+            if self.policy(&grant.policy).is_none() {
+                return Err(GolemError::new(format!(
+                    "Grant for account {} refers to unknown policy {}",
+                    grant.account_id, grant.policy
+                )));
+            }
+        }
+
+        // Policies can only be created for a grant, an unused one would silently be left out
+        for policy in &self.policies {
+            if !self
+                .grants
+                .iter()
+                .flatten()
+                .any(|g| g.policy == policy.name)
+            {
+                return Err(GolemError::new(format!(
+                    "Policy {} is not used by any grant in manifest",
+                    policy.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifest::spec::Manifest;
+
+    fn validate(yaml: &str) -> Result<(), String> {
+        let manifest: Manifest = serde_yaml::from_str(yaml).unwrap();
+        manifest.validate().map_err(|e| e.message)
+    }
+
+    #[test]
+    fn workers_and_deployment_sites_must_be_unique() {
+        let workers = "
+workers:
+  - name: w1
+    template: t1
+  - name: w1
+    template: t2
+";
+        assert_eq!(validate(workers), Ok(()));
+        assert_eq!(
+            validate(&format!("{workers}  - name: w1\n    template: t1\n")),
+            Err("Duplicate worker t1/w1 in manifest".to_string())
+        );
+
+        assert_eq!(
+            validate(
+                "
+deployments:
+  - definitionId: api1
+    host: example.com
+    subdomain: www
+  - definitionId: api2
+    host: example.com
+    subdomain: www
+"
+            ),
+            Err("Duplicate deployment site www.example.com in manifest".to_string())
+        );
+    }
+
+    #[test]
+    fn policies_must_be_used_by_a_grant() {
+        let policies = "
+policies:
+  - name: viewer
+    actions: [ViewTemplate]
+  - name: editor
+    actions: [UpdateTemplate]
+";
+
+        assert_eq!(
+            validate(&format!(
+                "{policies}grants:\n  - accountId: alice\n    policy: viewer\n"
+            )),
+            Err("Policy editor is not used by any grant in manifest".to_string())
+        );
+        assert_eq!(
+            validate(policies),
+            Err("Policy viewer is not used by any grant in manifest".to_string())
+        );
+    }
+}
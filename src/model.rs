@@ -27,6 +27,9 @@ use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 use uuid::Uuid;
 
+use crate::query::{self, Query};
+use crate::table::{self, TableColumns};
+
 pub enum GolemResult {
     Ok(Box<dyn PrintRes>),
     Json(serde_json::value::Value),
@@ -35,32 +38,115 @@ pub enum GolemResult {
 
 impl GolemResult {
     pub fn err(s: String) -> Result<GolemResult, GolemError> {
-        Err(GolemError(s))
+        Err(GolemError::new(s))
+    }
+}
+
+/// How results are printed, from `--format` and the other output arguments.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Output {
+    pub format: Format,
+    /// Columns of the table format, the type's default columns when empty
+    pub columns: Vec<String>,
+    pub query: Option<Query>,
+    /// Prints strings without quotes
+    pub raw: bool,
+}
+
+impl Output {
+    /// Prints a serialized result, using the type's default columns for tables when given.
+    pub fn print(&self, value: serde_json::Value, type_columns: &[&str]) -> Result<(), GolemError> {
+        // A query changes the shape of the result, so the type's columns no longer apply
+        let (value, type_columns) = match &self.query {
+            Some(query) => (query.apply(value)?, &[][..]),
+            None => (value, type_columns),
+        };
+
+        if self.raw {
+            if let Some(raw) = query::raw(&value) {
+                println!("{raw}");
+                return Ok(());
+            }
+        }
+
+        match self.format {
+            Format::Json => println!("{}", serde_json::to_string_pretty(&value).unwrap()),
+            Format::Yaml => println!("{}", serde_yaml::to_string(&value).unwrap()),
+            Format::Table => println!("{}", table::render(value, &self.columns, type_columns)?),
+        }
+
+        Ok(())
     }
 }
 
 pub trait PrintRes {
-    fn println(&self, format: &Format);
+    fn println(&self, output: &Output) -> Result<(), GolemError>;
 }
 
 impl<T> PrintRes for T
 where
-    T: Serialize,
+    T: Serialize + TableColumns,
 {
-    fn println(&self, format: &Format) {
-        match format {
-            Format::Json => println!("{}", serde_json::to_string_pretty(self).unwrap()),
-            Format::Yaml => println!("{}", serde_yaml::to_string(self).unwrap()),
+    fn println(&self, output: &Output) -> Result<(), GolemError> {
+        match output.format {
+            Format::Json if output.query.is_none() && !output.raw => {
+                println!("{}", serde_json::to_string_pretty(self).unwrap())
+            }
+            Format::Yaml if output.query.is_none() && !output.raw => {
+                println!("{}", serde_yaml::to_string(self).unwrap())
+            }
+            _ => output.print(serde_json::to_value(self).unwrap(), T::COLUMNS)?,
         }
+
+        Ok(())
     }
 }
 
 #[derive(Clone, PartialEq, Eq)]
-pub struct GolemError(pub String);
+pub struct GolemError {
+    pub message: String,
+    /// HTTP status of the API response the error was made from
+    pub status: Option<u16>,
+}
+
+/// Exit code when no usable credentials are available without user interaction.
+pub const EXIT_NOT_AUTHENTICATED: i32 = 3;
+
+impl GolemError {
+    pub fn new(message: String) -> GolemError {
+        GolemError {
+            message,
+            status: None,
+        }
+    }
+
+    fn response(status: u16, message: String) -> GolemError {
+        GolemError {
+            message,
+            status: Some(status),
+        }
+    }
+
+    pub fn not_authenticated(reason: &str) -> GolemError {
+        GolemError::new(format!(
+            "Not authenticated: {reason}. Set GOLEM_CLOUD_TOKEN, pass --auth-token-file or run `auth login` in a terminal"
+        ))
+    }
+
+    /// Whether the error is one created by [GolemError::not_authenticated].
+    pub fn is_not_authenticated(&self) -> bool {
+        self.message.starts_with("Not authenticated: ")
+    }
+
+    /// Whether the server rejected the token.
+    pub fn is_unauthorized(&self) -> bool {
+        self.status == Some(401)
+    }
+}
 
 impl From<reqwest::Error> for GolemError {
     fn from(error: reqwest::Error) -> Self {
-        GolemError(format!("Unexpected reqwest error: {error}"))
+        GolemError::new(format!("Unexpected reqwest error: {error}"))
     }
 }
 
@@ -71,22 +157,27 @@ impl<T: crate::clients::gateway::errors::ResponseContentErrorMapper>
         match value {
             golem_gateway_client::Error::Reqwest(error) => GolemError::from(error),
             golem_gateway_client::Error::Serde(error) => {
-                GolemError(format!("Unexpected serde error: {error}"))
+                GolemError::new(format!("Unexpected serde error: {error}"))
             }
             golem_gateway_client::Error::Item(data) => {
+                let status =
+                    crate::clients::gateway::errors::ResponseContentErrorMapper::status(&data);
                 let error_str =
                     crate::clients::gateway::errors::ResponseContentErrorMapper::map(data);
-                GolemError(format!("Response error: {error_str}"))
+                GolemError::response(status, format!("Response error: {error_str}"))
             }
             golem_gateway_client::Error::Unexpected { code, data } => {
-                match String::from_utf8(Vec::from(data)) {
-                    Ok(data_string) => GolemError(format!(
-                        "Unexpected http error. Code: {code}, content: {data_string}."
-                    )),
-                    Err(_) => GolemError(format!(
-                        "Unexpected http error. Code: {code}, can't parse content as string."
-                    )),
-                }
+                let message = match String::from_utf8(Vec::from(data)) {
+                    Ok(data_string) => {
+                        format!("Unexpected http error. Code: {code}, content: {data_string}.")
+                    }
+                    Err(_) => {
+                        format!(
+                            "Unexpected http error. Code: {code}, can't parse content as string."
+                        )
+                    }
+                };
+                GolemError::response(code, message)
             }
         }
     }
@@ -99,21 +190,25 @@ impl<T: crate::clients::errors::ResponseContentErrorMapper> From<golem_cloud_cli
         match value {
             golem_cloud_client::Error::Reqwest(error) => GolemError::from(error),
             golem_cloud_client::Error::Serde(error) => {
-                GolemError(format!("Unexpected serde error: {error}"))
+                GolemError::new(format!("Unexpected serde error: {error}"))
             }
             golem_cloud_client::Error::Item(data) => {
+                let status = crate::clients::errors::ResponseContentErrorMapper::status(&data);
                 let error_str = crate::clients::errors::ResponseContentErrorMapper::map(data);
-                GolemError(format!("Response error: {error_str}"))
+                GolemError::response(status, format!("Response error: {error_str}"))
             }
             golem_cloud_client::Error::Unexpected { code, data } => {
-                match String::from_utf8(Vec::from(data)) {
-                    Ok(data_string) => GolemError(format!(
-                        "Unexpected http error. Code: {code}, content: {data_string}."
-                    )),
-                    Err(_) => GolemError(format!(
-                        "Unexpected http error. Code: {code}, can't parse content as string."
-                    )),
-                }
+                let message = match String::from_utf8(Vec::from(data)) {
+                    Ok(data_string) => {
+                        format!("Unexpected http error. Code: {code}, content: {data_string}.")
+                    }
+                    Err(_) => {
+                        format!(
+                            "Unexpected http error. Code: {code}, can't parse content as string."
+                        )
+                    }
+                };
+                GolemError::response(code, message)
             }
         }
     }
@@ -121,30 +216,28 @@ impl<T: crate::clients::errors::ResponseContentErrorMapper> From<golem_cloud_cli
 
 impl Display for GolemError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let GolemError(s) = self;
-        Display::fmt(s, f)
+        Display::fmt(&self.message, f)
     }
 }
 
 impl Debug for GolemError {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let GolemError(s) = self;
-        Display::fmt(s, f)
+        Display::fmt(&self.message, f)
     }
 }
 
 impl std::error::Error for GolemError {
     fn description(&self) -> &str {
-        let GolemError(s) = self;
-
-        s
+        &self.message
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Format {
     Json,
     Yaml,
+    Table,
 }
 
 impl Display for Format {
@@ -152,6 +245,7 @@ impl Display for Format {
         let s = match self {
             Self::Json => "json",
             Self::Yaml => "yaml",
+            Self::Table => "table",
         };
         Display::fmt(&s, f)
     }
@@ -164,6 +258,7 @@ impl FromStr for Format {
         match s {
             "json" => Ok(Format::Json),
             "yaml" => Ok(Format::Yaml),
+            "table" => Ok(Format::Table),
             _ => {
                 let all = Format::iter()
                     .map(|x| format!("\"{x}\""))
@@ -382,6 +477,8 @@ pub enum Role {
     InstanceServer,
 }
 
+impl TableColumns for Role {}
+
 impl Display for Role {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -419,7 +516,7 @@ impl FromStr for Role {
     }
 }
 
-#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter, Serialize, Deserialize)]
 pub enum ProjectAction {
     ViewTemplate,
     CreateTemplate,
@@ -432,6 +529,23 @@ pub enum ProjectAction {
     ViewProjectGrants,
     CreateProjectGrants,
     DeleteProjectGrants,
+    ViewApiDefinition,
+    CreateApiDefinition,
+    UpdateApiDefinition,
+    DeleteApiDefinition,
+}
+
+impl ProjectAction {
+    /// Actions that remove resources or other accounts' access.
+    pub fn is_destructive(&self) -> bool {
+        matches!(
+            self,
+            ProjectAction::DeleteTemplate
+                | ProjectAction::DeleteWorker
+                | ProjectAction::DeleteProjectGrants
+                | ProjectAction::DeleteApiDefinition
+        )
+    }
 }
 
 impl Display for ProjectAction {
@@ -448,6 +562,10 @@ impl Display for ProjectAction {
             ProjectAction::ViewProjectGrants => "ViewProjectGrants",
             ProjectAction::CreateProjectGrants => "CreateProjectGrants",
             ProjectAction::DeleteProjectGrants => "DeleteProjectGrants",
+            ProjectAction::ViewApiDefinition => "ViewApiDefinition",
+            ProjectAction::CreateApiDefinition => "CreateApiDefinition",
+            ProjectAction::UpdateApiDefinition => "UpdateApiDefinition",
+            ProjectAction::DeleteApiDefinition => "DeleteApiDefinition",
         };
 
         Display::fmt(s, f)
@@ -470,6 +588,10 @@ impl FromStr for ProjectAction {
             "ViewProjectGrants" => Ok(ProjectAction::ViewProjectGrants),
             "CreateProjectGrants" => Ok(ProjectAction::CreateProjectGrants),
             "DeleteProjectGrants" => Ok(ProjectAction::DeleteProjectGrants),
+            "ViewApiDefinition" => Ok(ProjectAction::ViewApiDefinition),
+            "CreateApiDefinition" => Ok(ProjectAction::CreateApiDefinition),
+            "UpdateApiDefinition" => Ok(ProjectAction::UpdateApiDefinition),
+            "DeleteApiDefinition" => Ok(ProjectAction::DeleteApiDefinition),
             _ => {
                 let all = ProjectAction::iter()
                     .map(|x| format!("\"{x}\""))
@@ -484,12 +606,17 @@ impl FromStr for ProjectAction {
 #[derive(Clone, PartialEq, Eq, Debug, Display, FromStr)]
 pub struct ProjectPolicyId(pub Uuid);
 
+#[derive(Clone, PartialEq, Eq, Debug, Display, FromStr)]
+pub struct ProjectGrantId(pub Uuid);
+
 #[derive(Clone, PartialEq, Eq, Debug, Display, FromStr)]
 pub struct WorkerName(pub String); // TODO: Validate
 
 #[derive(Clone, PartialEq, Eq, Debug, Display, FromStr, Serialize)]
 pub struct InvocationKey(pub String); // TODO: Validate
 
+impl TableColumns for InvocationKey {}
+
 #[derive(Clone)]
 pub struct JsonValueParser;
 
@@ -534,6 +661,8 @@ pub struct ExampleDescription {
     pub tier: GuestLanguageTier,
 }
 
+impl TableColumns for ExampleDescription {}
+
 impl ExampleDescription {
     pub fn from_example(example: &Example) -> Self {
         Self {
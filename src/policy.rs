@@ -14,9 +14,16 @@
 
 use async_trait::async_trait;
 use clap::Subcommand;
+use golem_cloud_client::model::ProjectPolicy;
+use uuid::Uuid;
 
+use crate::clients::action_api_to_cli;
 use crate::clients::policy::ProjectPolicyClient;
-use crate::model::{GolemError, GolemResult, ProjectAction, ProjectPolicyId};
+use crate::clients::project::ProjectClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::model::{
+    AccountId, GolemError, GolemResult, ProjectAction, ProjectGrantId, ProjectPolicyId, ProjectRef,
+};
 
 #[derive(Subcommand, Debug)]
 #[command()]
@@ -39,6 +46,40 @@ pub enum ProjectPolicySubcommand {
         #[arg(value_name = "ID")]
         project_policy_id: ProjectPolicyId,
     },
+
+    /// Lists the sharing policies used by the grants of a project
+    #[command()]
+    List {
+        /// The project whose grants are inspected
+        #[command(flatten)]
+        project_ref: ProjectRef,
+    },
+
+    /// Replaces a policy with a revised copy and moves the project's grants to it
+    ///
+    /// Policies can't be changed in place, so the original policy is kept for grants of other
+    /// projects.
+    #[command()]
+    Update {
+        #[arg(value_name = "ID")]
+        project_policy_id: ProjectPolicyId,
+
+        /// The project whose grants are moved to the revised policy
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// New name of the policy
+        #[arg(long)]
+        project_policy_name: Option<String>,
+
+        /// Actions to allow in addition to the current ones
+        #[arg(long)]
+        add_action: Vec<ProjectAction>,
+
+        /// Actions to no longer allow
+        #[arg(long)]
+        remove_action: Vec<ProjectAction>,
+    },
 }
 
 #[async_trait]
@@ -46,12 +87,25 @@ pub trait ProjectPolicyHandler {
     async fn handle(&self, subcommand: ProjectPolicySubcommand) -> Result<GolemResult, GolemError>;
 }
 
-pub struct ProjectPolicyHandlerLive<C: ProjectPolicyClient + Send + Sync> {
+pub struct ProjectPolicyHandlerLive<
+    'p,
+    C: ProjectPolicyClient + Send + Sync,
+    P: ProjectClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+> {
     pub client: C,
+    pub projects: &'p P,
+    pub grants: G,
 }
 
 #[async_trait]
-impl<C: ProjectPolicyClient + Send + Sync> ProjectPolicyHandler for ProjectPolicyHandlerLive<C> {
+impl<
+        'p,
+        C: ProjectPolicyClient + Send + Sync,
+        P: ProjectClient + Sync + Send,
+        G: ProjectGrantClient + Sync + Send,
+    > ProjectPolicyHandler for ProjectPolicyHandlerLive<'p, C, P, G>
+{
     async fn handle(&self, subcommand: ProjectPolicySubcommand) -> Result<GolemResult, GolemError> {
         match subcommand {
             ProjectPolicySubcommand::Add {
@@ -70,6 +124,73 @@ impl<C: ProjectPolicyClient + Send + Sync> ProjectPolicyHandler for ProjectPolic
 
                 Ok(GolemResult::Ok(Box::new(policy)))
             }
+            ProjectPolicySubcommand::List { project_ref } => {
+                let project_id = self.projects.resolve_id_or_default(project_ref).await?;
+
+                let mut policy_ids: Vec<Uuid> = Vec::new();
+                for grant in self.grants.find(project_id).await? {
+                    if !policy_ids.contains(&grant.data.project_policy_id) {
+                        policy_ids.push(grant.data.project_policy_id);
+                    }
+                }
+
+                let mut policies: Vec<ProjectPolicy> = Vec::new();
+                for policy_id in policy_ids {
+                    policies.push(self.client.get(ProjectPolicyId(policy_id)).await?);
+                }
+
+                Ok(GolemResult::Ok(Box::new(policies)))
+            }
+            ProjectPolicySubcommand::Update {
+                project_policy_id,
+                project_ref,
+                project_policy_name,
+                add_action,
+                remove_action,
+            } => {
+                let project_id = self.projects.resolve_id_or_default(project_ref).await?;
+                let policy = self.client.get(project_policy_id.clone()).await?;
+
+                let current: Vec<ProjectAction> = policy
+                    .project_actions
+                    .actions
+                    .into_iter()
+                    .map(action_api_to_cli)
+                    .collect();
+                let mut actions = current.clone();
+                for action in add_action {
+                    if !actions.contains(&action) {
+                        actions.push(action);
+                    }
+                }
+                actions.retain(|a| !remove_action.contains(a));
+
+                let name = project_policy_name.unwrap_or(policy.name.clone());
+                if name == policy.name && actions == current {
+                    return Err(GolemError::new(format!(
+                        "Nothing to update, policy {name} already has this name and these actions"
+                    )));
+                }
+
+                let updated = self.client.create(name, actions).await?;
+
+                // The new grant is created first, so the grantee never loses access
+                for grant in self.grants.find(project_id.clone()).await? {
+                    if grant.data.project_policy_id == project_policy_id.0 {
+                        let account_id = AccountId {
+                            id: grant.data.grantee_account_id,
+                        };
+                        self.grants
+                            .create(project_id.clone(), account_id, ProjectPolicyId(updated.id))
+                            .await?;
+                        self.grants
+                            .delete(project_id.clone(), ProjectGrantId(grant.id))
+                            .await?;
+                    }
+                }
+
+                Ok(GolemResult::Ok(Box::new(updated)))
+            }
         }
     }
 }
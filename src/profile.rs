@@ -0,0 +1,224 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use async_trait::async_trait;
+use clap::Subcommand;
+use reqwest::Url;
+use serde::Serialize;
+
+use crate::auth::store::CredentialStore;
+use crate::config::{parse_profile_name, Config, Profile};
+use crate::model::{Format, GolemError, GolemResult};
+use crate::table::TableColumns;
+
+#[derive(Subcommand, Debug)]
+#[command()]
+pub enum ProfileSubcommand {
+    /// Lists the configured profiles
+    #[command()]
+    List {},
+
+    /// Sets the profile used when `--profile` is not given
+    #[command()]
+    Use {
+        /// Name of the profile
+        #[arg(
+            value_name = "PROFILE",
+            value_parser = parse_profile_name,
+            required_unless_present = "clear"
+        )]
+        name: Option<String>,
+
+        /// Stops using a profile, going back to the environment variables and default settings
+        #[arg(long, conflicts_with = "name")]
+        clear: bool,
+    },
+
+    /// Adds a new profile
+    #[command()]
+    Add {
+        /// Name of the profile
+        #[arg(value_name = "PROFILE", value_parser = parse_profile_name)]
+        name: String,
+
+        /// Golem Cloud API URL, the release environment is used when missing
+        #[arg(long)]
+        base_url: Option<Url>,
+
+        /// API gateway URL, the base URL is used when missing
+        #[arg(long)]
+        gateway_url: Option<Url>,
+
+        /// Token to authenticate with, OAuth2 login is used when missing
+        #[arg(long)]
+        auth_token: Option<uuid::Uuid>,
+
+        /// Output format used when `--format` is not given
+        #[arg(long)]
+        format: Option<Format>,
+    },
+
+    /// Removes a profile with its stored authentication
+    #[command()]
+    Remove {
+        /// Name of the profile
+        #[arg(value_name = "PROFILE", value_parser = parse_profile_name)]
+        name: String,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileView {
+    pub name: String,
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gateway_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<String>,
+}
+
+impl TableColumns for ProfileView {}
+
+impl ProfileView {
+    fn new(name: &str, profile: &Profile, active: bool) -> ProfileView {
+        ProfileView {
+            name: name.to_string(),
+            active,
+            base_url: profile.base_url.clone(),
+            gateway_url: profile.gateway_url.clone(),
+            project_name: profile.project.as_ref().map(|p| p.name.clone()),
+            format: profile.format.map(|f| f.to_string()),
+        }
+    }
+}
+
+/// Removes the stored authentication of a profile, refusing anything that isn't a directory
+/// directly inside the profiles directory, such as a symlink.
+fn remove_profile_dir(config_dir: &Path, name: &str) -> Result<(), GolemError> {
+    let dir = Config::profile_dir(config_dir, name)?;
+    let metadata = match dir.symlink_metadata() {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(GolemError::new(format!("Failed to access {dir:?}: {err}"))),
+    };
+
+    let canonical = |path: &Path| {
+        path.canonicalize()
+            .map_err(|e| GolemError::new(format!("Failed to access {path:?}: {e}")))
+    };
+    let profiles_dir = canonical(&Config::profiles_dir(config_dir))?;
+    let parent = canonical(dir.parent().unwrap_or(&dir))?;
+
+    if !metadata.is_dir() || parent != profiles_dir {
+        return Err(GolemError::new(format!(
+            "Not removing {dir:?}, it is not a profile directory"
+        )));
+    }
+
+    std::fs::remove_dir_all(&dir)
+        .map_err(|e| GolemError::new(format!("Failed to remove {dir:?}: {e}")))
+}
+
+#[async_trait]
+pub trait ProfileHandler {
+    async fn handle(&self, subcommand: ProfileSubcommand) -> Result<GolemResult, GolemError>;
+}
+
+pub struct ProfileHandlerLive {
+    pub config_dir: PathBuf,
+}
+
+#[async_trait]
+impl ProfileHandler for ProfileHandlerLive {
+    async fn handle(&self, subcommand: ProfileSubcommand) -> Result<GolemResult, GolemError> {
+        let mut config = Config::read(&self.config_dir)?;
+
+        match subcommand {
+            ProfileSubcommand::List {} => {
+                let profiles: Vec<ProfileView> = config
+                    .profiles
+                    .iter()
+                    .map(|(name, profile)| {
+                        let active = config.active_profile.as_ref() == Some(name);
+                        ProfileView::new(name, profile, active)
+                    })
+                    .collect();
+
+                Ok(GolemResult::Ok(Box::new(profiles)))
+            }
+            ProfileSubcommand::Use { name, clear: _ } => match name {
+                None => {
+                    config.active_profile = None;
+                    config.store(&self.config_dir)?;
+
+                    Ok(GolemResult::Str("Not using a profile".to_string()))
+                }
+                Some(name) => {
+                    config.profile(&name)?;
+                    config.active_profile = Some(name.clone());
+                    config.store(&self.config_dir)?;
+
+                    Ok(GolemResult::Str(format!("Using profile {name}")))
+                }
+            },
+            ProfileSubcommand::Add {
+                name,
+                base_url,
+                gateway_url,
+                auth_token,
+                format,
+            } => {
+                if config.profiles.contains_key(&name) {
+                    return Err(GolemError::new(format!("Profile {name} already exists")));
+                }
+
+                let profile = Profile {
+                    base_url: base_url.map(|u| u.to_string()),
+                    gateway_url: gateway_url.map(|u| u.to_string()),
+                    auth_token,
+                    project: None,
+                    format,
+                };
+                let view = ProfileView::new(&name, &profile, false);
+
+                config.profiles.insert(name, profile);
+                config.store(&self.config_dir)?;
+
+                Ok(GolemResult::Ok(Box::new(view)))
+            }
+            ProfileSubcommand::Remove { name } => {
+                config.profile(&name)?;
+                // The keyring entry outlives the directory, which only holds file stores
+                CredentialStore::Keyring.delete(&Config::profile_dir(&self.config_dir, &name)?)?;
+                remove_profile_dir(&self.config_dir, &name)?;
+
+                config.profiles.remove(&name);
+                if config.active_profile.as_ref() == Some(&name) {
+                    config.active_profile = None;
+                }
+                config.store(&self.config_dir)?;
+
+                Ok(GolemResult::Str(format!("Removed profile {name}")))
+            }
+        }
+    }
+}
@@ -12,12 +12,47 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod access;
+mod bundle;
+mod grants;
+
+use std::path::PathBuf;
+
 use async_trait::async_trait;
 use clap::Subcommand;
+use golem_gateway_client::{Context, Security};
+use itertools::Itertools;
+use serde::Serialize;
+use uuid::Uuid;
 
+use crate::clients::gateway::definition::DefinitionClientLive;
+use crate::clients::gateway::deployment::DeploymentClientLive;
+use crate::clients::policy::ProjectPolicyClient;
 use crate::clients::project::ProjectClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::clients::template::TemplateClient;
+use crate::clients::worker::WorkerClient;
 use crate::clients::CloudAuthentication;
-use crate::model::{AccountId, GolemError, GolemResult};
+use crate::config::{Config, PinnedProject};
+use crate::confirm;
+use crate::manifest::plan::Planner;
+use crate::model::{AccountId, GolemError, GolemResult, ProjectId, ProjectRef, WorkerName};
+use crate::project::access::ReportFormat;
+use crate::project::grants::{ProjectGrantsHandlerLive, ProjectGrantsSubcommand};
+use crate::table::TableColumns;
+
+fn parse_worker_ref(s: &str) -> Result<(String, WorkerName), String> {
+    s.split_once('/')
+        .map(|(template, worker)| (template.to_string(), WorkerName(worker.to_string())))
+        .ok_or_else(|| format!("invalid TEMPLATE/WORKER: no `/` found in `{s}`"))
+}
+
+fn parse_project_ref(s: &str) -> Result<ProjectRef, String> {
+    Ok(match Uuid::parse_str(s) {
+        Ok(id) => ProjectRef::Id(ProjectId(id)),
+        Err(_) => ProjectRef::Name(s.to_string()),
+    })
+}
 
 #[derive(Subcommand, Debug)]
 #[command()]
@@ -42,11 +77,151 @@ pub enum ProjectSubcommand {
         project_name: Option<String>,
     },
 
-    /// Gets the default project which is used when no explicit project is specified
+    /// Gets the account's default project, used when no explicit or pinned project is specified
     #[command()]
     GetDefault {},
+
+    /// Pins a project locally, to be used when no explicit project is specified
+    #[command()]
+    Use {
+        /// Name or identifier of the project
+        #[arg(value_name = "PROJECT", required_unless_present = "clear")]
+        project: Option<String>,
+
+        /// Removes the pinned project, going back to the account's default project
+        #[arg(long, conflicts_with = "project")]
+        clear: bool,
+    },
+
+    /// Shows the project used when no explicit project is specified, and where it comes from
+    #[command()]
+    Current {},
+
+    /// Gets a project by its identifier or name
+    #[command()]
+    Get {
+        /// The project to get
+        #[command(flatten)]
+        project_ref: ProjectRef,
+    },
+
+    /// Deletes a project with all of its templates and workers
+    #[command()]
+    Delete {
+        /// The project to delete
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// Delete without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+
+    /// Lists and revokes the grants sharing a project with other accounts
+    #[command()]
+    Grants {
+        #[command(subcommand)]
+        subcommand: ProjectGrantsSubcommand,
+    },
+
+    /// Revokes all access of an account to a project
+    #[command()]
+    Unshare {
+        /// The project to stop sharing
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The account to revoke access from
+        #[arg(long)]
+        account_id: AccountId,
+    },
+
+    /// Shows which actions each account may take in a project
+    ///
+    /// Accounts holding destructive actions, such as deleting templates or workers, are flagged.
+    #[command()]
+    AccessReport {
+        /// The project to report on
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// Output of the report: table, csv or json
+        #[arg(long, default_value = "table")]
+        report_format: ReportFormat,
+    },
+
+    /// Exports the templates, workers, grants and API definitions of a project to a bundle
+    #[command()]
+    Export {
+        /// The project to export
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The bundle directory, or a tarball if it ends with .tar.gz or .tgz
+        #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+        output: PathBuf,
+
+        /// Workers to include in template-name/worker-name format, as workers can't be listed
+        #[arg(short, long, value_parser = parse_worker_ref)]
+        worker: Vec<(String, WorkerName)>,
+    },
+
+    /// Recreates the content of an exported bundle in a project
+    ///
+    /// A project given by name is created if it doesn't exist yet.
+    #[command()]
+    Import {
+        /// The project to import into
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The bundle directory or tarball
+        #[arg(short, long, value_hint = clap::ValueHint::AnyPath)]
+        input: PathBuf,
+    },
+
+    /// Copies the templates, workers, policies, grants and API definitions of a project into a new project
+    ///
+    /// Only workers given with --worker are recreated, as workers can't be listed. API deployments
+    /// are not copied, as their sites belong to the source project. The new project is deleted
+    /// again if the copy fails as a whole.
+    #[command()]
+    Clone {
+        /// The project to copy, by name or id
+        #[arg(long, value_parser = parse_project_ref)]
+        from: ProjectRef,
+
+        /// Name of the new project
+        #[arg(long)]
+        to: String,
+
+        /// Workers to recreate in template-name/worker-name format, as workers can't be listed
+        #[arg(short, long, value_parser = parse_worker_ref)]
+        worker: Vec<(String, WorkerName)>,
+    },
 }
 
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneSummaryView {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub copied: usize,
+    pub not_copied: Vec<String>,
+}
+
+impl TableColumns for CloneSummaryView {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CurrentProjectView {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub source: String,
+}
+
+impl TableColumns for CurrentProjectView {}
+
 #[async_trait]
 pub trait ProjectHandler {
     async fn handle(
@@ -56,17 +231,106 @@ pub trait ProjectHandler {
     ) -> Result<GolemResult, GolemError>;
 }
 
-pub struct ProjectHandlerLive<'c, C: ProjectClient + Send + Sync> {
+pub struct ProjectHandlerLive<
+    'c,
+    C: ProjectClient + Send + Sync,
+    T: TemplateClient + Sync + Send,
+    W: WorkerClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+> {
     pub client: &'c C,
+    pub templates: T,
+    pub workers: W,
+    pub grants: G,
+    pub policies: Po,
+    pub gateway_url: reqwest::Url,
+    pub http_client: reqwest::Client,
+    pub config_dir: PathBuf,
+    /// The profile in use, which pinned projects are stored in
+    pub profile: Option<String>,
+}
+
+impl<
+        'c,
+        C: ProjectClient + Send + Sync,
+        T: TemplateClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        G: ProjectGrantClient + Sync + Send,
+        Po: ProjectPolicyClient + Sync + Send,
+    > ProjectHandlerLive<'c, C, T, W, G, Po>
+{
+    async fn find_or_create(
+        &self,
+        auth: &CloudAuthentication,
+        project_ref: ProjectRef,
+    ) -> Result<ProjectId, GolemError> {
+        match project_ref {
+            ProjectRef::Name(name) => {
+                let existing = self
+                    .client
+                    .find(Some(name.clone()))
+                    .await?
+                    .into_iter()
+                    .find(|p| p.project_data.name == name);
+
+                match existing {
+                    Some(project) => Ok(ProjectId(project.project_id)),
+                    None => {
+                        let project = self.client.create(auth.account_id(), name, None).await?;
+                        Ok(ProjectId(project.project_id))
+                    }
+                }
+            }
+            project_ref => self.client.resolve_id_or_default(project_ref).await,
+        }
+    }
 }
 
 #[async_trait]
-impl<'c, C: ProjectClient + Send + Sync> ProjectHandler for ProjectHandlerLive<'c, C> {
+impl<
+        'c,
+        C: ProjectClient + Send + Sync,
+        T: TemplateClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        G: ProjectGrantClient + Sync + Send,
+        Po: ProjectPolicyClient + Sync + Send,
+    > ProjectHandler for ProjectHandlerLive<'c, C, T, W, G, Po>
+{
     async fn handle(
         &self,
         auth: &CloudAuthentication,
         subcommand: ProjectSubcommand,
     ) -> Result<GolemResult, GolemError> {
+        let context = Context {
+            base_url: self.gateway_url.clone(),
+            client: self.http_client.clone(),
+            security_token: Security::Bearer(auth.0.secret.value.to_string()),
+        };
+        let definitions = DefinitionClientLive {
+            client: golem_gateway_client::api::ApiDefinitionClientLive {
+                context: context.clone(),
+            },
+        };
+        let deployments = DeploymentClientLive {
+            client: golem_gateway_client::api::ApiDeploymentClientLive {
+                context: context.clone(),
+            },
+        };
+        let grants = ProjectGrantsHandlerLive {
+            projects: self.client,
+            grants: &self.grants,
+            policies: &self.policies,
+        };
+        let planner = Planner {
+            templates: &self.templates,
+            workers: &self.workers,
+            grants: &self.grants,
+            policies: &self.policies,
+            definitions: &definitions,
+            deployments: &deployments,
+        };
+
         match subcommand {
             ProjectSubcommand::Add {
                 project_name,
@@ -95,6 +359,210 @@ impl<'c, C: ProjectClient + Send + Sync> ProjectHandler for ProjectHandlerLive<'
 
                 Ok(GolemResult::Ok(Box::new(project)))
             }
+            ProjectSubcommand::Use { project, clear: _ } => {
+                let mut config = Config::read(&self.config_dir)?;
+
+                match project {
+                    None => {
+                        config.pin_project(self.profile.as_deref(), None)?;
+                        config.store(&self.config_dir)?;
+
+                        Ok(GolemResult::Str(
+                            "Using the account's default project".to_string(),
+                        ))
+                    }
+                    Some(project) => {
+                        let project_ref = match Uuid::parse_str(&project) {
+                            Ok(id) => ProjectRef::Id(ProjectId(id)),
+                            Err(_) => ProjectRef::Name(project),
+                        };
+                        let project_id = self.client.resolve_id_or_default(project_ref).await?;
+                        let project = self.client.get(project_id).await?;
+
+                        config.pin_project(
+                            self.profile.as_deref(),
+                            Some(PinnedProject {
+                                id: project.project_id,
+                                name: project.project_data.name.clone(),
+                            }),
+                        )?;
+                        config.store(&self.config_dir)?;
+
+                        Ok(GolemResult::Ok(Box::new(project)))
+                    }
+                }
+            }
+            ProjectSubcommand::Current {} => {
+                let config = Config::read(&self.config_dir)?;
+
+                let current = match config.pinned_project(self.profile.as_deref()) {
+                    Some(pinned) => CurrentProjectView {
+                        project_id: pinned.id,
+                        project_name: pinned.name.clone(),
+                        source: match &self.profile {
+                            Some(profile) => format!(
+                                "pinned in profile {profile} of {}",
+                                Config::path(&self.config_dir).display()
+                            ),
+                            None => {
+                                format!("pinned in {}", Config::path(&self.config_dir).display())
+                            }
+                        },
+                    },
+                    None => {
+                        let project = self.client.find_default().await?;
+
+                        CurrentProjectView {
+                            project_id: project.project_id,
+                            project_name: project.project_data.name,
+                            source: "account default".to_string(),
+                        }
+                    }
+                };
+
+                Ok(GolemResult::Ok(Box::new(current)))
+            }
+            ProjectSubcommand::Get { project_ref } => {
+                let project = match self.client.resolve_id(project_ref).await? {
+                    None => self.client.find_default().await?,
+                    Some(project_id) => self.client.get(project_id).await?,
+                };
+
+                Ok(GolemResult::Ok(Box::new(project)))
+            }
+            ProjectSubcommand::Delete { project_ref, yes } => {
+                let project_id = self.client.resolve_id_or_default(project_ref).await?;
+                let project = self.client.get(project_id.clone()).await?;
+
+                if !yes {
+                    let templates = self
+                        .templates
+                        .find(Some(project_id.clone()), None)
+                        .await?
+                        .into_iter()
+                        .into_group_map_by(|t| (t.template_name.clone(), t.template_id.clone()));
+
+                    if templates.is_empty() {
+                        eprintln!("Project {} has no templates", project.project_data.name);
+                    } else {
+                        eprintln!(
+                            "Deleting project {} also deletes these templates and all of their workers:",
+                            project.project_data.name
+                        );
+                        for ((name, id), versions) in
+                            templates.iter().sorted_by(|a, b| a.0.cmp(b.0))
+                        {
+                            eprintln!("  {name} ({id}), {} versions", versions.len());
+                        }
+                    }
+
+                    if !confirm(&format!("Delete project {}?", project.project_data.name))? {
+                        return Ok(GolemResult::Str("Delete cancelled".to_string()));
+                    }
+                }
+
+                self.client.delete(project_id.clone()).await?;
+
+                let mut config = Config::read(&self.config_dir)?;
+                if config.project_id(self.profile.as_deref()) == Some(project_id) {
+                    config.pin_project(self.profile.as_deref(), None)?;
+                    config.store(&self.config_dir)?;
+                }
+
+                Ok(GolemResult::Str(format!(
+                    "Deleted project {}",
+                    project.project_data.name
+                )))
+            }
+            ProjectSubcommand::Grants { subcommand } => grants.handle(subcommand).await,
+            ProjectSubcommand::Unshare {
+                project_ref,
+                account_id,
+            } => {
+                let project_id = self.client.resolve_id_or_default(project_ref).await?;
+
+                grants.unshare(project_id, account_id).await
+            }
+            ProjectSubcommand::AccessReport {
+                project_ref,
+                report_format,
+            } => {
+                let project_id = self.client.resolve_id_or_default(project_ref).await?;
+
+                grants.handle_access_report(project_id, report_format).await
+            }
+            ProjectSubcommand::Export {
+                project_ref,
+                output,
+                worker,
+            } => {
+                let project_id = self.client.resolve_id_or_default(project_ref).await?;
+                let info = planner.export(project_id, worker, &output).await?;
+
+                Ok(GolemResult::Ok(Box::new(info)))
+            }
+            ProjectSubcommand::Import { project_ref, input } => {
+                let project_id = self.find_or_create(auth, project_ref).await?;
+                let applied = planner.import(project_id.clone(), &input).await?;
+
+                Ok(GolemResult::Str(format!(
+                    "Imported {applied} changes into project {}",
+                    project_id.0
+                )))
+            }
+            ProjectSubcommand::Clone { from, to, worker } => {
+                let from_id = self.client.resolve_id_or_default(from).await?;
+
+                let exists = self
+                    .client
+                    .find(Some(to.clone()))
+                    .await?
+                    .into_iter()
+                    .any(|p| p.project_data.name == to);
+                if exists {
+                    return Err(GolemError::new(format!("Project {to} already exists")));
+                }
+
+                let project = self
+                    .client
+                    .create(auth.account_id(), to.clone(), None)
+                    .await?;
+                let project_id = ProjectId(project.project_id);
+
+                let dir = tempfile::Builder::new()
+                    .prefix("golem-clone-")
+                    .tempdir()
+                    .map_err(|e| {
+                        GolemError::new(format!("Failed to create a temporary directory: {e}"))
+                    })?;
+                let copy = async {
+                    planner.export(from_id, worker, dir.path()).await?;
+                    planner.import_all(project_id.clone(), dir.path()).await
+                };
+
+                let (copied, not_copied) = match copy.await {
+                    Ok(result) => result,
+                    Err(GolemError { message: err, .. }) => {
+                        // Don't leave a half-filled project behind
+                        return Err(match self.client.delete(project_id.clone()).await {
+                            Ok(()) => GolemError::new(format!(
+                                "Failed to clone into project {to}, which was deleted again: {err}"
+                            )),
+                            Err(GolemError { message: delete_err, .. }) => GolemError::new(format!(
+                                "Failed to clone into project {to} ({}): {err}. Deleting it failed as well: {delete_err}",
+                                project_id.0
+                            )),
+                        });
+                    }
+                };
+
+                Ok(GolemResult::Ok(Box::new(CloneSummaryView {
+                    project_id: project_id.0,
+                    project_name: to,
+                    copied,
+                    not_copied,
+                })))
+            }
         }
     }
 }
@@ -0,0 +1,228 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use serde::Serialize;
+use strum::IntoEnumIterator;
+use strum_macros::EnumIter;
+use uuid::Uuid;
+
+use crate::clients::policy::ProjectPolicyClient;
+use crate::clients::project::ProjectClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::model::{GolemError, GolemResult, ProjectAction, ProjectId};
+use crate::project::grants::ProjectGrantsHandlerLive;
+use crate::table;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, EnumIter)]
+pub enum ReportFormat {
+    Table,
+    Csv,
+    Json,
+}
+
+impl Display for ReportFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Self::Table => "table",
+            Self::Csv => "csv",
+            Self::Json => "json",
+        };
+        Display::fmt(&s, f)
+    }
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "table" => Ok(ReportFormat::Table),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => {
+                let all = ReportFormat::iter()
+                    .map(|x| format!("\"{x}\""))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                Err(format!("Unknown report format: {s}. Expected one of {all}"))
+            }
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccessReport {
+    pub project_id: Uuid,
+    pub accounts: Vec<AccountAccess>,
+}
+
+/// Effective permissions of one account, combined from all of its grants.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountAccess {
+    pub account_id: String,
+    /// Names of the policies granting the actions, `owner` for the project owner
+    pub sources: Vec<String>,
+    pub actions: Vec<ProjectAction>,
+    pub destructive: bool,
+}
+
+impl AccountAccess {
+    fn new(account_id: String) -> AccountAccess {
+        AccountAccess {
+            account_id,
+            sources: Vec::new(),
+            actions: Vec::new(),
+            destructive: false,
+        }
+    }
+
+    fn add(&mut self, source: String, actions: impl IntoIterator<Item = ProjectAction>) {
+        if !self.sources.contains(&source) {
+            self.sources.push(source);
+        }
+        for action in actions {
+            if !self.actions.contains(&action) {
+                self.actions.push(action);
+            }
+        }
+        self.destructive = self.actions.iter().any(|a| a.is_destructive());
+    }
+}
+
+impl AccessReport {
+    fn header() -> Vec<String> {
+        let mut header = vec!["Account".to_string(), "Sources".to_string()];
+        header.extend(ProjectAction::iter().map(|a| a.to_string()));
+        header.push("Destructive".to_string());
+        header
+    }
+
+    fn rows(&self, mark: &str) -> Vec<Vec<String>> {
+        self.accounts
+            .iter()
+            .map(|account| {
+                let mut row = vec![account.account_id.clone(), account.sources.join(" ")];
+                row.extend(ProjectAction::iter().map(|a| {
+                    if account.actions.contains(&a) {
+                        mark.to_string()
+                    } else {
+                        String::new()
+                    }
+                }));
+                row.push(if account.destructive { "yes" } else { "" }.to_string());
+                row
+            })
+            .collect()
+    }
+
+    pub fn to_csv(&self) -> String {
+        fn field(value: &str) -> String {
+            if value.contains([',', '"', '\n']) {
+                format!("\"{}\"", value.replace('"', "\"\""))
+            } else {
+                value.to_string()
+            }
+        }
+
+        let mut lines = vec![AccessReport::header().join(",")];
+        for row in self.rows("true") {
+            lines.push(row.iter().map(|v| field(v)).collect::<Vec<_>>().join(","));
+        }
+        lines.join("\n")
+    }
+
+    pub fn to_table(&self) -> Result<String, GolemError> {
+        let header = AccessReport::header();
+        let rows: Vec<serde_json::Value> = self
+            .rows("x")
+            .into_iter()
+            .map(|row| {
+                header
+                    .iter()
+                    .cloned()
+                    .zip(row.into_iter().map(serde_json::Value::String))
+                    .collect::<serde_json::Map<_, _>>()
+                    .into()
+            })
+            .collect();
+
+        table::render(serde_json::Value::Array(rows), &header, &[])
+    }
+}
+
+impl<'a, P, G, Po> ProjectGrantsHandlerLive<'a, P, G, Po>
+where
+    P: ProjectClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+{
+    /// Combines the owner and the grants of a project into the actions each account may take.
+    pub async fn access_report(&self, project_id: ProjectId) -> Result<AccessReport, GolemError> {
+        let project = self.projects.get(project_id.clone()).await?;
+
+        let mut owner = AccountAccess::new(project.project_data.owner_account_id);
+        owner.add("owner".to_string(), ProjectAction::iter());
+        let mut accounts = vec![owner];
+
+        let grants = self.grants.find(project_id.clone()).await?;
+        for view in self.views(grants).await? {
+            let index = match accounts
+                .iter()
+                .position(|a| a.account_id == view.grantee_account_id)
+            {
+                Some(index) => index,
+                None => {
+                    accounts.push(AccountAccess::new(view.grantee_account_id));
+                    accounts.len() - 1
+                }
+            };
+            accounts[index].add(view.policy_name, view.actions);
+        }
+
+        for account in accounts.iter_mut() {
+            account.actions.sort_by_key(|a| {
+                ProjectAction::iter()
+                    .position(|b| b == *a)
+                    .unwrap_or(usize::MAX)
+            });
+        }
+
+        Ok(AccessReport {
+            project_id: project_id.0,
+            accounts,
+        })
+    }
+
+    pub async fn handle_access_report(
+        &self,
+        project_id: ProjectId,
+        format: ReportFormat,
+    ) -> Result<GolemResult, GolemError> {
+        let report = self.access_report(project_id).await?;
+
+        match format {
+            ReportFormat::Table => Ok(GolemResult::Str(report.to_table()?)),
+            ReportFormat::Csv => Ok(GolemResult::Str(report.to_csv())),
+            ReportFormat::Json => Ok(GolemResult::Str(
+                serde_json::to_string_pretty(&report)
+                    .map_err(|e| GolemError::new(format!("Failed to serialize the report: {e}")))?,
+            )),
+        }
+    }
+}
@@ -0,0 +1,424 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use golem_cloud_client::model::ProjectPolicy;
+use itertools::Itertools;
+use serde::{Deserialize, Serialize};
+use tempfile::TempDir;
+use uuid::Uuid;
+
+use crate::clients::action_api_to_cli;
+use crate::clients::gateway::definition::DefinitionClient;
+use crate::clients::gateway::deployment::DeploymentClient;
+use crate::clients::policy::ProjectPolicyClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::clients::template::TemplateClient;
+use crate::clients::worker::WorkerClient;
+use crate::manifest::plan::{Plan, Planner};
+use crate::manifest::spec::{
+    DefinitionSpec, GrantSpec, Manifest, PolicySpec, TemplateSpec, WorkerSpec,
+};
+use crate::model::{GolemError, ProjectId, ProjectPolicyId, RawTemplateId, WorkerName};
+use crate::table::TableColumns;
+
+const MANIFEST_FILE: &str = "golem.yaml";
+const INFO_FILE: &str = "bundle.yaml";
+
+/// Where the bundle came from, used to remap template ids on import.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleInfo {
+    pub project_id: Uuid,
+    pub templates: Vec<BundledTemplate>,
+}
+
+impl TableColumns for BundleInfo {}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BundledTemplate {
+    pub name: String,
+    pub id: Uuid,
+    pub version: i32,
+}
+
+fn is_tarball(path: &Path) -> bool {
+    let name = path.to_string_lossy();
+    name.ends_with(".tar.gz") || name.ends_with(".tgz")
+}
+
+fn write_bundle(output: &Path, files: Vec<(String, Vec<u8>)>) -> Result<(), GolemError> {
+    let write_err = |e: std::io::Error| GolemError::new(format!("Failed to write {output:?}: {e}"));
+
+    if is_tarball(output) {
+        let file = File::create(output).map_err(write_err)?;
+        let mut tar = tar::Builder::new(GzEncoder::new(file, Compression::default()));
+
+        for (name, bytes) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(bytes.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            tar.append_data(&mut header, name, bytes.as_slice())
+                .map_err(write_err)?;
+        }
+
+        tar.into_inner()
+            .and_then(|gz| gz.finish())
+            .map_err(write_err)?;
+    } else {
+        for (name, bytes) in files {
+            let path = output.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(write_err)?;
+            }
+            std::fs::write(&path, bytes).map_err(write_err)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn to_yaml<S: Serialize>(value: &S) -> Result<Vec<u8>, GolemError> {
+    serde_yaml::to_string(value)
+        .map(String::into_bytes)
+        .map_err(|e| GolemError::new(format!("Failed to serialize bundle: {e}")))
+}
+
+/// Unpacks into a fresh private directory, removed when dropped.
+fn unpack(input: &Path) -> Result<TempDir, GolemError> {
+    let dir = tempfile::Builder::new()
+        .prefix("golem-bundle-")
+        .tempdir()
+        .map_err(|e| GolemError::new(format!("Failed to create a temporary directory: {e}")))?;
+    let file =
+        File::open(input).map_err(|e| GolemError::new(format!("Failed to open {input:?}: {e}")))?;
+
+    tar::Archive::new(GzDecoder::new(file))
+        .unpack(dir.path())
+        .map_err(|e| GolemError::new(format!("Failed to unpack {input:?}: {e}")))?;
+
+    Ok(dir)
+}
+
+/// Template files have to be in the bundle, so importing one can't upload other local files.
+fn check_inside(dir: &Path, manifest: &Manifest) -> Result<(), GolemError> {
+    let canonical = |path: &Path| {
+        path.canonicalize()
+            .map_err(|e| GolemError::new(format!("Failed to access {path:?}: {e}")))
+    };
+    let dir = canonical(dir)?;
+
+    for template in &manifest.templates {
+        if !canonical(&template.file)?.starts_with(&dir) {
+            return Err(GolemError::new(format!(
+                "Template file {:?} of template {} is outside of the bundle",
+                template.file, template.name
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+impl<'a, T, W, G, Po, D, De> Planner<'a, T, W, G, Po, D, De>
+where
+    T: TemplateClient + Sync + Send,
+    W: WorkerClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+    D: DefinitionClient + Sync + Send,
+    De: DeploymentClient + Sync + Send,
+{
+    /// Writes the latest version of every template, the given workers, the grants with their
+    /// policies and the API definitions of a project as a manifest with its WASM files.
+    ///
+    /// Deployments are left out, as their sites belong to the source environment.
+    pub async fn export(
+        &self,
+        project_id: ProjectId,
+        workers: Vec<(String, WorkerName)>,
+        output: &Path,
+    ) -> Result<BundleInfo, GolemError> {
+        let mut files = Vec::new();
+        let mut manifest = Manifest {
+            grants: Some(Vec::new()),
+            api_definitions: Some(Vec::new()),
+            ..Default::default()
+        };
+        let mut info = BundleInfo {
+            project_id: project_id.0,
+            templates: Vec::new(),
+        };
+
+        let templates = self
+            .templates
+            .find(Some(project_id.clone()), None)
+            .await?
+            .into_iter()
+            .into_group_map_by(|t| t.template_name.clone());
+
+        for (name, versions) in templates.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+            if versions.iter().map(|t| &t.template_id).unique().count() > 1 {
+                return Err(GolemError::new(format!(
+                    "Multiple templates found for name {name}, they can't be exported by name"
+                )));
+            }
+
+            let latest = versions
+                .into_iter()
+                .max_by_key(|t| t.template_version)
+                .unwrap();
+            let id = Uuid::parse_str(&latest.template_id)
+                .map_err(|err| GolemError::new(format!("Failed to parse template id: {err}")))?;
+            let file = format!("templates/{id}.wasm");
+
+            files.push((
+                file.clone(),
+                self.templates
+                    .download(&RawTemplateId(id), Some(latest.template_version))
+                    .await?,
+            ));
+            manifest.templates.push(TemplateSpec {
+                name: name.clone(),
+                file: PathBuf::from(file),
+            });
+            info.templates.push(BundledTemplate {
+                name,
+                id,
+                version: latest.template_version,
+            });
+        }
+
+        for (template, worker) in workers {
+            let template_id = info
+                .templates
+                .iter()
+                .find(|t| t.name == template)
+                .map(|t| RawTemplateId(t.id))
+                .ok_or_else(|| GolemError::new(format!("Can't find template {template}")))?;
+            let metadata = self
+                .workers
+                .find_metadata(worker.clone(), template_id)
+                .await?
+                .ok_or_else(|| GolemError::new(format!("Can't find worker {template}/{worker}")))?;
+
+            manifest.workers.push(WorkerSpec {
+                name: worker.0,
+                template,
+                args: metadata.args,
+                env: metadata.env.into_iter().collect(),
+            });
+        }
+
+        let mut policies: HashMap<Uuid, ProjectPolicy> = HashMap::new();
+        for grant in self.grants.find(project_id.clone()).await? {
+            let policy_id = grant.data.project_policy_id;
+            if let Entry::Vacant(entry) = policies.entry(policy_id) {
+                entry.insert(self.policies.get(ProjectPolicyId(policy_id)).await?);
+            }
+            let policy = &policies[&policy_id];
+
+            let spec = PolicySpec {
+                name: policy.name.clone(),
+                actions: policy
+                    .project_actions
+                    .actions
+                    .iter()
+                    .cloned()
+                    .map(action_api_to_cli)
+                    .collect(),
+            };
+            match manifest.policy(&spec.name) {
+                None => manifest.policies.push(spec),
+                Some(existing) if *existing == spec => {}
+                Some(_) => {
+                    return Err(GolemError::new(format!(
+                        "Multiple policies named {}, they can't be exported by name",
+                        spec.name
+                    )))
+                }
+            }
+
+            manifest
+                .grants
+                .get_or_insert_with(Vec::new)
+                .push(GrantSpec {
+                    account_id: grant.data.grantee_account_id,
+                    policy: policy.name.clone(),
+                });
+        }
+
+        for definition in self.definitions.get(project_id, None).await? {
+            manifest
+                .api_definitions
+                .get_or_insert_with(Vec::new)
+                .push(DefinitionSpec {
+                    id: definition.id,
+                    version: definition.version,
+                    routes: definition.routes,
+                });
+        }
+
+        files.push((MANIFEST_FILE.to_string(), to_yaml(&manifest)?));
+        files.push((INFO_FILE.to_string(), to_yaml(&info)?));
+
+        write_bundle(output, files)?;
+
+        Ok(info)
+    }
+
+    /// Recreates the content of a bundle in a project, keeping what the project already has.
+    pub async fn import(&self, project_id: ProjectId, input: &Path) -> Result<usize, GolemError> {
+        self.import_bundle(project_id, input, false)
+            .await
+            .map(|(applied, _)| applied)
+    }
+
+    /// Like [import](Self::import), but carries on past failed changes and returns them.
+    pub async fn import_all(
+        &self,
+        project_id: ProjectId,
+        input: &Path,
+    ) -> Result<(usize, Vec<String>), GolemError> {
+        self.import_bundle(project_id, input, true).await
+    }
+
+    async fn import_bundle(
+        &self,
+        project_id: ProjectId,
+        input: &Path,
+        keep_going: bool,
+    ) -> Result<(usize, Vec<String>), GolemError> {
+        if is_tarball(input) {
+            let dir = unpack(input)?;
+            self.import_dir(project_id, dir.path(), keep_going).await
+        } else {
+            self.import_dir(project_id, input, keep_going).await
+        }
+    }
+
+    async fn apply_plan(
+        &self,
+        plan: Plan,
+        keep_going: bool,
+    ) -> Result<(usize, Vec<String>), GolemError> {
+        if keep_going {
+            Ok(self.apply_all(plan).await)
+        } else {
+            Ok((self.apply(plan).await?, Vec::new()))
+        }
+    }
+
+    async fn import_dir(
+        &self,
+        project_id: ProjectId,
+        dir: &Path,
+        keep_going: bool,
+    ) -> Result<(usize, Vec<String>), GolemError> {
+        let mut manifest = Manifest::load(&dir.join(MANIFEST_FILE))?;
+        manifest.project = None;
+        check_inside(dir, &manifest)?;
+
+        let info_path = dir.join(INFO_FILE);
+        let info: BundleInfo = File::open(&info_path)
+            .map_err(|e| GolemError::new(format!("Failed to open {info_path:?}: {e}")))
+            .and_then(|file| {
+                serde_yaml::from_reader(file)
+                    .map_err(|e| GolemError::new(format!("Failed to parse {info_path:?}: {e}")))
+            })?;
+
+        // Templates go first, as API definitions refer to them by id
+        let templates_only = Manifest {
+            templates: manifest.templates.clone(),
+            ..Default::default()
+        };
+        let mut plan = self.plan(project_id.clone(), &templates_only).await?;
+        plan.only_creations();
+        let (mut applied, mut failed) = self.apply_plan(plan, keep_going).await?;
+
+        let new_ids: HashMap<String, String> = self
+            .templates
+            .find(Some(project_id.clone()), None)
+            .await?
+            .into_iter()
+            .map(|t| (t.template_name, t.template_id))
+            .collect();
+        let mut remap: HashMap<Uuid, Uuid> = HashMap::new();
+        for template in info.templates {
+            if let Some(id) = new_ids.get(&template.name) {
+                let id = Uuid::parse_str(id).map_err(|err| {
+                    GolemError::new(format!("Failed to parse template id: {err}"))
+                })?;
+                remap.insert(template.id, id);
+            }
+        }
+
+        for definition in manifest.api_definitions.iter_mut().flatten() {
+            for route in definition.routes.iter_mut() {
+                if let Some(id) = remap.get(&route.binding.template) {
+                    route.binding.template = *id;
+                }
+            }
+        }
+
+        let mut plan = self.plan(project_id, &manifest).await?;
+        plan.only_creations();
+        let (more_applied, more_failed) = self.apply_plan(plan, keep_going).await?;
+        applied += more_applied;
+        failed.extend(more_failed);
+
+        Ok((applied, failed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::manifest::spec::{Manifest, TemplateSpec};
+    use crate::project::bundle::check_inside;
+
+    #[test]
+    fn template_files_must_be_inside_the_bundle() {
+        let outside = tempfile::tempdir().unwrap();
+        let bundle = tempfile::tempdir().unwrap();
+        std::fs::write(outside.path().join("secret.wasm"), b"").unwrap();
+        std::fs::write(bundle.path().join("t.wasm"), b"").unwrap();
+
+        let manifest = |file: &str| Manifest {
+            templates: vec![TemplateSpec {
+                name: "t".to_string(),
+                file: bundle.path().join(file),
+            }],
+            ..Manifest::default()
+        };
+        let escape = format!(
+            "../{}/secret.wasm",
+            outside.path().file_name().unwrap().to_string_lossy()
+        );
+
+        assert!(check_inside(bundle.path(), &manifest("t.wasm")).is_ok());
+        assert!(check_inside(bundle.path(), &manifest(&escape))
+            .unwrap_err()
+            .message
+            .ends_with("of template t is outside of the bundle"));
+    }
+}
@@ -0,0 +1,172 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use clap::Subcommand;
+use golem_cloud_client::model::{ProjectGrant, ProjectPolicy};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::clients::action_api_to_cli;
+use crate::clients::policy::ProjectPolicyClient;
+use crate::clients::project::ProjectClient;
+use crate::clients::project_grant::ProjectGrantClient;
+use crate::model::{
+    AccountId, GolemError, GolemResult, ProjectAction, ProjectGrantId, ProjectId, ProjectPolicyId,
+    ProjectRef,
+};
+use crate::table::TableColumns;
+
+#[derive(Subcommand, Debug)]
+#[command()]
+pub enum ProjectGrantsSubcommand {
+    /// Lists the accounts a project is shared with
+    #[command()]
+    List {
+        /// The project to list the grants of
+        #[command(flatten)]
+        project_ref: ProjectRef,
+    },
+
+    /// Revokes a single project grant
+    #[command()]
+    Revoke {
+        /// The project the grant belongs to
+        #[command(flatten)]
+        project_ref: ProjectRef,
+
+        /// The grant's identifier
+        #[arg(value_name = "GRANT_ID")]
+        grant_id: ProjectGrantId,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectGrantView {
+    pub grant_id: Uuid,
+    pub grantee_account_id: String,
+    pub policy_id: Uuid,
+    pub policy_name: String,
+    pub actions: Vec<ProjectAction>,
+}
+
+impl TableColumns for ProjectGrantView {}
+
+impl ProjectGrantView {
+    fn new(grant: ProjectGrant, policy: &ProjectPolicy) -> ProjectGrantView {
+        ProjectGrantView {
+            grant_id: grant.id,
+            grantee_account_id: grant.data.grantee_account_id,
+            policy_id: policy.id,
+            policy_name: policy.name.clone(),
+            actions: policy
+                .project_actions
+                .actions
+                .iter()
+                .cloned()
+                .map(action_api_to_cli)
+                .collect(),
+        }
+    }
+}
+
+pub struct ProjectGrantsHandlerLive<'a, P, G, Po> {
+    pub projects: &'a P,
+    pub grants: &'a G,
+    pub policies: &'a Po,
+}
+
+impl<'a, P, G, Po> ProjectGrantsHandlerLive<'a, P, G, Po>
+where
+    P: ProjectClient + Sync + Send,
+    G: ProjectGrantClient + Sync + Send,
+    Po: ProjectPolicyClient + Sync + Send,
+{
+    /// Grants only refer to their policy, so the policies are fetched to show the actions.
+    pub(super) async fn views(
+        &self,
+        grants: Vec<ProjectGrant>,
+    ) -> Result<Vec<ProjectGrantView>, GolemError> {
+        let mut policies: HashMap<Uuid, ProjectPolicy> = HashMap::new();
+        let mut views = Vec::new();
+
+        for grant in grants {
+            let policy_id = grant.data.project_policy_id;
+            if let Entry::Vacant(entry) = policies.entry(policy_id) {
+                entry.insert(self.policies.get(ProjectPolicyId(policy_id)).await?);
+            }
+
+            views.push(ProjectGrantView::new(grant, &policies[&policy_id]));
+        }
+
+        Ok(views)
+    }
+
+    pub async fn handle(
+        &self,
+        subcommand: ProjectGrantsSubcommand,
+    ) -> Result<GolemResult, GolemError> {
+        match subcommand {
+            ProjectGrantsSubcommand::List { project_ref } => {
+                let project_id = self.projects.resolve_id_or_default(project_ref).await?;
+                let grants = self.grants.find(project_id).await?;
+
+                Ok(GolemResult::Ok(Box::new(self.views(grants).await?)))
+            }
+            ProjectGrantsSubcommand::Revoke {
+                project_ref,
+                grant_id,
+            } => {
+                let project_id = self.projects.resolve_id_or_default(project_ref).await?;
+                self.grants.delete(project_id, grant_id.clone()).await?;
+
+                Ok(GolemResult::Str(format!("Revoked grant {grant_id}")))
+            }
+        }
+    }
+
+    /// Revokes every grant of an account on a project.
+    pub async fn unshare(
+        &self,
+        project_id: ProjectId,
+        account_id: AccountId,
+    ) -> Result<GolemResult, GolemError> {
+        let grants: Vec<ProjectGrant> = self
+            .grants
+            .find(project_id.clone())
+            .await?
+            .into_iter()
+            .filter(|g| g.data.grantee_account_id == account_id.id)
+            .collect();
+
+        if grants.is_empty() {
+            return Err(GolemError::new(format!(
+                "Project {} is not shared with account {account_id}",
+                project_id.0
+            )));
+        }
+
+        let views = self.views(grants).await?;
+        for view in &views {
+            self.grants
+                .delete(project_id.clone(), ProjectGrantId(view.grant_id))
+                .await?;
+        }
+
+        Ok(GolemResult::Ok(Box::new(views)))
+    }
+}
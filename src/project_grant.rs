@@ -28,6 +28,7 @@ pub trait ProjectGrantHandler {
         recipient_account_id: AccountId,
         project_policy_id: Option<ProjectPolicyId>,
         project_actions: Option<Vec<ProjectAction>>,
+        project_policy_name: Option<String>,
     ) -> Result<GolemResult, GolemError>;
 }
 pub struct ProjectGrantHandlerLive<
@@ -49,6 +50,7 @@ impl<'p, C: ProjectGrantClient + Send + Sync, P: ProjectClient + Sync + Send> Pr
         recipient_account_id: AccountId,
         project_policy_id: Option<ProjectPolicyId>,
         project_actions: Option<Vec<ProjectAction>>,
+        project_policy_name: Option<String>,
     ) -> Result<GolemResult, GolemError> {
         let project_id = match self.project.resolve_id(project_ref).await? {
             None => ProjectId(self.project.find_default().await?.project_id),
@@ -60,7 +62,12 @@ impl<'p, C: ProjectGrantClient + Send + Sync, P: ProjectClient + Sync + Send> Pr
 
                 let grant = self
                     .client
-                    .create_actions(project_id, recipient_account_id, actions)
+                    .create_actions(
+                        project_id,
+                        recipient_account_id,
+                        actions,
+                        project_policy_name,
+                    )
                     .await?;
 
                 Ok(GolemResult::Ok(Box::new(grant)))
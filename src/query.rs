@@ -0,0 +1,307 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::str::FromStr;
+
+use serde_json::Value;
+
+use crate::model::GolemError;
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+enum Segment {
+    Field(String),
+    Index(i64),
+    Iterate,
+}
+
+/// A path into the result, in a subset of jq and JSONPath.
+///
+/// Supports `.field`, `["field"]`, `[0]`, `[-1]` and `[]` or `[*]` to go through an array,
+/// optionally starting with `$`, like `.[].templateId` or `$[*].templateId`.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Query {
+    source: String,
+    segments: Vec<Segment>,
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let chars: Vec<char> = s.trim().chars().collect();
+        let err = |pos: usize, expected: &str| {
+            Err(format!(
+                "Invalid query {s}: expected {expected} at position {pos}"
+            ))
+        };
+
+        let mut segments = Vec::new();
+        let mut pos = if chars.first() == Some(&'$') { 1 } else { 0 };
+
+        while pos < chars.len() {
+            match chars[pos] {
+                '.' => {
+                    pos += 1;
+                    let start = pos;
+                    while pos < chars.len()
+                        && (chars[pos].is_alphanumeric() || chars[pos] == '_' || chars[pos] == '-')
+                    {
+                        pos += 1;
+                    }
+                    if pos > start {
+                        segments.push(Segment::Field(chars[start..pos].iter().collect()));
+                    } else if pos < chars.len() && chars[pos] != '[' {
+                        return err(pos, "a field name or [");
+                    }
+                }
+                '[' => {
+                    pos += 1;
+                    let end = match chars[pos..].iter().position(|c| *c == ']') {
+                        Some(len) => pos + len,
+                        None => return err(pos, "]"),
+                    };
+                    let inner: String = chars[pos..end].iter().collect();
+                    let inner = inner.trim();
+
+                    let segment = if inner.is_empty() || inner == "*" {
+                        Segment::Iterate
+                    } else if let Ok(index) = inner.parse::<i64>() {
+                        Segment::Index(index)
+                    } else if inner.len() >= 2
+                        && (inner.starts_with('"') && inner.ends_with('"')
+                            || inner.starts_with('\'') && inner.ends_with('\''))
+                    {
+                        Segment::Field(inner[1..inner.len() - 1].to_string())
+                    } else {
+                        return err(pos, "an index, a quoted field name, * or ]");
+                    };
+
+                    segments.push(segment);
+                    pos = end + 1;
+                }
+                _ => return err(pos, ". or ["),
+            }
+        }
+
+        Ok(Query {
+            source: s.to_string(),
+            segments,
+        })
+    }
+}
+
+impl Query {
+    /// Applies the query, collecting the results into an array once it went through one.
+    pub fn apply(&self, value: Value) -> Result<Value, GolemError> {
+        let mut values = vec![value];
+        let mut iterated = false;
+
+        for segment in &self.segments {
+            let mut next = Vec::new();
+
+            for value in values {
+                match (segment, value) {
+                    (Segment::Iterate, Value::Array(items)) => next.extend(items),
+                    (Segment::Iterate, Value::Object(fields)) => {
+                        next.extend(fields.into_iter().map(|(_, v)| v))
+                    }
+                    (Segment::Iterate, other) => {
+                        return Err(GolemError::new(format!(
+                            "Query {} can't go through {}",
+                            self.source,
+                            type_name(&other)
+                        )))
+                    }
+                    (segment, value) => next.push(Self::step(segment, value, &self.source)?),
+                }
+            }
+
+            iterated |= *segment == Segment::Iterate;
+            values = next;
+        }
+
+        if iterated {
+            Ok(Value::Array(values))
+        } else {
+            Ok(values.into_iter().next().unwrap_or(Value::Null))
+        }
+    }
+
+    /// Missing fields and indices give null, like in jq.
+    fn step(segment: &Segment, value: Value, source: &str) -> Result<Value, GolemError> {
+        match (segment, value) {
+            (_, Value::Null) => Ok(Value::Null),
+            (Segment::Field(name), Value::Object(mut fields)) => {
+                Ok(fields.remove(name.as_str()).unwrap_or(Value::Null))
+            }
+            (Segment::Index(index), Value::Array(mut items)) => {
+                let len = items.len() as i64;
+                let index = if *index < 0 { len + index } else { *index };
+                if (0..len).contains(&index) {
+                    Ok(items.swap_remove(index as usize))
+                } else {
+                    Ok(Value::Null)
+                }
+            }
+            (Segment::Field(name), other) => Err(GolemError::new(format!(
+                "Query {source} can't get field {name} of {}",
+                type_name(&other)
+            ))),
+            (_, other) => Err(GolemError::new(format!(
+                "Query {source} can't index {}",
+                type_name(&other)
+            ))),
+        }
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+fn raw_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some("null".to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::String(s) => Some(s.clone()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+/// Scalars without quotes, and arrays of scalars one per line, like `jq -r`.
+pub fn raw(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(items) => items
+            .iter()
+            .map(raw_scalar)
+            .collect::<Option<Vec<_>>>()
+            .map(|lines| lines.join("\n")),
+        value => raw_scalar(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::query::{raw, Query};
+
+    fn apply(query: &str, value: serde_json::Value) -> Result<serde_json::Value, String> {
+        let query: Query = query.parse()?;
+        query.apply(value).map_err(|e| e.message)
+    }
+
+    fn templates() -> serde_json::Value {
+        json!([
+            {"templateId": "a", "templateName": "first", "versionedTemplateId": {"version": 1}},
+            {"templateId": "b", "templateName": "second", "versionedTemplateId": {"version": 2}},
+        ])
+    }
+
+    #[test]
+    fn iterates_arrays() {
+        assert_eq!(apply("[].templateId", templates()), Ok(json!(["a", "b"])));
+        assert_eq!(apply(".[].templateId", templates()), Ok(json!(["a", "b"])));
+        assert_eq!(apply("$[*].templateId", templates()), Ok(json!(["a", "b"])));
+        assert_eq!(
+            apply("[].templateId", json!({"x": {"templateId": "c"}})),
+            Ok(json!(["c"]))
+        );
+    }
+
+    #[test]
+    fn gets_nested_fields() {
+        assert_eq!(
+            apply("[].versionedTemplateId.version", templates()),
+            Ok(json!([1, 2]))
+        );
+        assert_eq!(
+            apply(r#"[0]["versionedTemplateId"]['version']"#, templates()),
+            Ok(json!(1))
+        );
+        assert_eq!(apply(".", json!({"a": 1})), Ok(json!({"a": 1})));
+    }
+
+    #[test]
+    fn indexes_from_either_end() {
+        assert_eq!(apply("[1].templateName", templates()), Ok(json!("second")));
+        assert_eq!(apply("[-1].templateName", templates()), Ok(json!("second")));
+        assert_eq!(apply("[-2].templateName", templates()), Ok(json!("first")));
+        assert_eq!(apply("[2]", templates()), Ok(json!(null)));
+    }
+
+    #[test]
+    fn missing_keys_give_null() {
+        assert_eq!(apply(".missing", json!({"a": 1})), Ok(json!(null)));
+        assert_eq!(apply(".missing.deeper", json!({"a": 1})), Ok(json!(null)));
+        assert_eq!(apply("[].missing", templates()), Ok(json!([null, null])));
+    }
+
+    #[test]
+    fn wrong_types_are_errors() {
+        assert_eq!(
+            apply(".a.b", json!({"a": 1})),
+            Err("Query .a.b can't get field b of a number".to_string())
+        );
+        assert_eq!(
+            apply(".a[0]", json!({"a": "text"})),
+            Err("Query .a[0] can't index a string".to_string())
+        );
+        assert_eq!(
+            apply(".a[]", json!({"a": true})),
+            Err("Query .a[] can't go through a boolean".to_string())
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_queries() {
+        assert_eq!(
+            "[0".parse::<Query>(),
+            Err("Invalid query [0: expected ] at position 1".to_string())
+        );
+        assert_eq!(
+            ".a[x]".parse::<Query>(),
+            Err(
+                "Invalid query .a[x]: expected an index, a quoted field name, * or ] at position 3"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            "a".parse::<Query>(),
+            Err("Invalid query a: expected . or [ at position 0".to_string())
+        );
+        assert_eq!(
+            ".!".parse::<Query>(),
+            Err("Invalid query .!: expected a field name or [ at position 1".to_string())
+        );
+    }
+
+    #[test]
+    fn raw_prints_scalars_without_quotes() {
+        assert_eq!(raw(&json!("text")), Some("text".to_string()));
+        assert_eq!(raw(&json!(1.5)), Some("1.5".to_string()));
+        assert_eq!(raw(&json!(null)), Some("null".to_string()));
+        assert_eq!(raw(&json!(["a", 1, true])), Some("a\n1\ntrue".to_string()));
+        assert_eq!(raw(&json!([{"a": 1}])), None);
+        assert_eq!(raw(&json!({"a": 1})), None);
+    }
+}
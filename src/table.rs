@@ -0,0 +1,362 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::io::IsTerminal;
+
+use golem_cloud_client::model::{
+    Account, Project, ProjectGrant, ProjectPolicy, Template, Token, UnsafeToken, VersionedWorkerId,
+    WorkerMetadata,
+};
+use golem_gateway_client::model::{ApiDefinition, ApiDeployment, ApiDomain, Certificate};
+use itertools::Itertools;
+use serde_json::Value;
+
+use crate::model::GolemError;
+
+const MIN_COLUMN_WIDTH: usize = 3;
+
+type Row = Vec<(String, Value)>;
+
+/// Flattens nested objects into dotted column names, keeping arrays in a single cell.
+fn flatten(prefix: Option<&str>, value: Value, row: &mut Row) {
+    match value {
+        Value::Object(fields) => {
+            for (key, value) in fields {
+                let name = match prefix {
+                    Some(prefix) => format!("{prefix}.{key}"),
+                    None => key,
+                };
+                flatten(Some(&name), value, row);
+            }
+        }
+        value => row.push((prefix.unwrap_or("value").to_string(), value)),
+    }
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        Value::Bool(b) => b.to_string(),
+        Value::Number(n) => n.to_string(),
+        Value::Array(items) if items.iter().all(|i| !i.is_array() && !i.is_object()) => {
+            items.iter().map(cell).collect::<Vec<_>>().join(", ")
+        }
+        Value::Array(_) | Value::Object(_) => value.to_string(),
+    }
+}
+
+/// Default table columns of a printed type, all of its scalar fields when empty.
+///
+/// Lists print with the columns of their items.
+pub trait TableColumns {
+    const COLUMNS: &'static [&'static str] = &[];
+}
+
+impl<T: TableColumns> TableColumns for Vec<T> {
+    const COLUMNS: &'static [&'static str] = T::COLUMNS;
+}
+
+impl TableColumns for String {}
+
+impl TableColumns for &str {}
+
+impl TableColumns for Account {}
+
+impl TableColumns for ProjectGrant {}
+
+impl TableColumns for ProjectPolicy {}
+
+impl TableColumns for VersionedWorkerId {}
+
+impl TableColumns for ApiDeployment {}
+
+impl TableColumns for ApiDomain {}
+
+impl TableColumns for Certificate {}
+
+impl TableColumns for Template {
+    const COLUMNS: &'static [&'static str] = &[
+        "versionedTemplateId.templateId",
+        "templateName",
+        "versionedTemplateId.version",
+        "templateSize",
+    ];
+}
+
+impl TableColumns for Project {
+    const COLUMNS: &'static [&'static str] = &[
+        "projectId",
+        "projectData.name",
+        "projectData.projectType",
+        "projectData.description",
+    ];
+}
+
+impl TableColumns for Token {
+    const COLUMNS: &'static [&'static str] = &["id", "createdAt", "expiresAt"];
+}
+
+// The secret is only shown in tables when asked for with --columns
+impl TableColumns for UnsafeToken {
+    const COLUMNS: &'static [&'static str] = &["data.id", "data.createdAt", "data.expiresAt"];
+}
+
+impl TableColumns for WorkerMetadata {
+    const COLUMNS: &'static [&'static str] = &[
+        "workerId.workerName",
+        "workerId.templateId",
+        "status",
+        "templateVersion",
+        "retryCount",
+    ];
+}
+
+impl TableColumns for ApiDefinition {
+    const COLUMNS: &'static [&'static str] = &["id", "version", "projectId"];
+}
+
+/// Columns of types without defaults, leaving out arrays as they tend to be long.
+fn flattened_columns(rows: &[Row]) -> Vec<String> {
+    let mut all: Vec<String> = Vec::new();
+    let mut scalar: Vec<String> = Vec::new();
+
+    for (name, value) in rows.iter().flatten() {
+        if !all.contains(name) {
+            all.push(name.clone());
+        }
+        if !value.is_array() && !scalar.contains(name) {
+            scalar.push(name.clone());
+        }
+    }
+
+    if scalar.is_empty() {
+        all
+    } else {
+        scalar
+    }
+}
+
+/// Width of the terminal stdout is printed to, when it is one.
+fn terminal_width() -> Option<usize> {
+    if let Some(width) = std::env::var("COLUMNS").ok().and_then(|c| c.parse().ok()) {
+        return Some(width);
+    }
+
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+
+    #[cfg(unix)]
+    {
+        let mut size: libc::winsize = unsafe { std::mem::zeroed() };
+        // SAFETY: TIOCGWINSZ only writes into the given winsize
+        let res = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut size) };
+        if res == 0 && size.ws_col > 0 {
+            return Some(size.ws_col as usize);
+        }
+    }
+
+    None
+}
+
+/// Shrinks the widest columns until the table fits into the given width.
+fn fit(widths: &mut [usize], max_width: usize) {
+    let separators = 2 * widths.len().saturating_sub(1);
+
+    while widths.iter().sum::<usize>() + separators > max_width {
+        match widths.iter_mut().max() {
+            Some(widest) if *widest > MIN_COLUMN_WIDTH => *widest -= 1,
+            _ => break,
+        }
+    }
+}
+
+fn truncate(s: &str, width: usize) -> String {
+    if s.chars().count() <= width {
+        s.to_string()
+    } else {
+        let mut truncated: String = s.chars().take(width - 1).collect();
+        truncated.push('…');
+        truncated
+    }
+}
+
+/// Renders a result as a table, one row per array item or a single row for an object.
+///
+/// Columns are the flattened field names, like `projectData.name`. Without given columns,
+/// the default columns of the type are used, or all of its scalar fields.
+pub fn render(
+    value: Value,
+    columns: &[String],
+    type_columns: &[&str],
+) -> Result<String, GolemError> {
+    let items = match value {
+        Value::Array(items) => items,
+        Value::Object(_) => vec![value],
+        scalar => return Ok(cell(&scalar)),
+    };
+
+    let rows: Vec<Row> = items
+        .into_iter()
+        .map(|item| {
+            let mut row = Vec::new();
+            flatten(None, item, &mut row);
+            row
+        })
+        .collect();
+
+    let mut known: Vec<&String> = Vec::new();
+    for (name, _) in rows.iter().flatten() {
+        if !known.contains(&name) {
+            known.push(name);
+        }
+    }
+
+    let columns = if !columns.is_empty() {
+        if let Some(unknown) = columns
+            .iter()
+            .find(|c| !rows.is_empty() && !known.contains(c))
+        {
+            return Err(GolemError::new(format!(
+                "Unknown column: {unknown}. Expected one of {}",
+                known.iter().join(", ")
+            )));
+        }
+        columns.to_vec()
+    } else {
+        let typed: Vec<String> = type_columns
+            .iter()
+            .filter(|c| known.iter().any(|k| k == *c))
+            .map(|c| c.to_string())
+            .collect();
+
+        if typed.is_empty() {
+            flattened_columns(&rows)
+        } else {
+            typed
+        }
+    };
+
+    let cells: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|column| {
+                    row.iter()
+                        .find(|(name, _)| name == column)
+                        .map(|(_, value)| cell(value))
+                        .unwrap_or_default()
+                })
+                .collect()
+        })
+        .collect();
+
+    let mut widths: Vec<usize> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            cells
+                .iter()
+                .map(|row| row[i].chars().count())
+                .chain([column.chars().count()])
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    if let Some(max_width) = terminal_width() {
+        fit(&mut widths, max_width);
+    }
+
+    let line = |row: &[String]| {
+        row.iter()
+            .zip(&widths)
+            .map(|(value, &width)| {
+                let value = truncate(value, width);
+                format!("{value:width$}")
+            })
+            .collect::<Vec<_>>()
+            .join("  ")
+            .trim_end()
+            .to_string()
+    };
+
+    let mut lines = vec![line(&columns)];
+    lines.extend(cells.iter().map(|row| line(row)));
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::table::{fit, render, truncate};
+
+    fn columns(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    fn projects() -> serde_json::Value {
+        json!([
+            {"projectId": "p1", "projectData": {"name": "first", "tags": ["a", "b"]}},
+            {"projectId": "p2", "projectData": {"name": "second", "tags": []}},
+        ])
+    }
+
+    #[test]
+    fn flattens_nested_fields_and_skips_arrays_by_default() {
+        assert_eq!(
+            render(projects(), &[], &[]).unwrap(),
+            "projectId  projectData.name\np1         first\np2         second"
+        );
+        assert_eq!(
+            render(json!({"a": 1, "b": {"c": null}}), &[], &[]).unwrap(),
+            "a  b.c\n1"
+        );
+        assert_eq!(render(json!("text"), &[], &[]).unwrap(), "text");
+    }
+
+    #[test]
+    fn uses_the_given_columns_before_the_type_columns() {
+        assert_eq!(
+            render(projects(), &columns(&["projectData.tags"]), &["projectId"]).unwrap(),
+            "projectData.tags\na, b\n"
+        );
+        assert_eq!(
+            render(projects(), &[], &["projectData.name", "missing"]).unwrap(),
+            "projectData.name\nfirst\nsecond"
+        );
+        assert_eq!(
+            render(projects(), &columns(&["name"]), &[])
+                .unwrap_err()
+                .message,
+            "Unknown column: name. Expected one of projectId, projectData.name, projectData.tags"
+        );
+    }
+
+    #[test]
+    fn shrinks_the_widest_columns_to_fit() {
+        let mut widths = vec![10, 4, 20];
+        fit(&mut widths, 24);
+        assert_eq!(widths, vec![8, 4, 8]);
+
+        let mut widths = vec![10, 10];
+        fit(&mut widths, 2);
+        assert_eq!(widths, vec![3, 3]);
+
+        assert_eq!(truncate("abcdef", 4), "abc…");
+        assert_eq!(truncate("abcd", 4), "abcd");
+    }
+}
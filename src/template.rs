@@ -12,18 +12,36 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod diff;
+mod schema;
+mod watch;
+mod wit;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use clap::Subcommand;
+use golem_cloud_client::model::Template;
 use indoc::formatdoc;
 use itertools::Itertools;
+use tracing::info;
 use uuid::Uuid;
 
+use crate::account::Usage;
+use crate::clients::account::AccountClient;
 use crate::clients::project::ProjectClient;
-use crate::clients::template::{TemplateClient, TemplateView};
+use crate::clients::template::{TemplateClient, TemplateVersionView, TemplateView};
+use crate::clients::worker::WorkerClient;
 use crate::model::{
-    GolemError, GolemResult, PathBufOrStdin, ProjectId, ProjectRef, RawTemplateId,
-    TemplateIdOrName, TemplateName,
+    AccountId, GolemError, GolemResult, Output, PathBufOrStdin, PrintRes, ProjectId, ProjectRef,
+    RawTemplateId, TemplateIdOrName, TemplateName, WorkerName,
 };
+use crate::template::diff::{diff_exports, exports_from_wasm, TemplateDiff};
+use crate::template::schema::function_schema;
+use crate::template::watch::{watch, WatchConfig};
+use crate::template::wit::render_wit;
 
 #[derive(Subcommand, Debug)]
 #[command()]
@@ -42,6 +60,11 @@ pub enum TemplateSubcommand {
         /// The WASM file to be used as a Golem template
         #[arg(value_name = "template-file", value_hint = clap::ValueHint::FilePath)]
         template_file: PathBufOrStdin, // TODO: validate exists
+
+        /// Warn first if the account is at its plan's template limit. Counting the templates
+        /// takes a request per project of the account.
+        #[arg(long)]
+        check_limit: bool,
     },
 
     /// Updates an existing template by uploading a new version of its WASM
@@ -67,33 +90,194 @@ pub enum TemplateSubcommand {
         #[arg(short, long)]
         template_name: Option<TemplateName>,
     },
+
+    /// Compares the exported functions of two versions of a template
+    ///
+    /// Fails if any function was removed or had its signature changed.
+    #[command()]
+    Diff {
+        /// The template name or identifier to compare
+        #[command(flatten)]
+        template_id_or_name: TemplateIdOrName,
+
+        /// The version to compare from, defaults to the version before `--to`, or to the latest version with `--wasm`
+        #[arg(long)]
+        from: Option<i32>,
+
+        /// The version to compare to, defaults to the latest version
+        #[arg(long, conflicts_with = "wasm")]
+        to: Option<i32>,
+
+        /// A local WASM file to compare to instead of an uploaded version
+        #[arg(long, value_hint = clap::ValueHint::FilePath, conflicts_with = "to")]
+        wasm: Option<PathBuf>,
+    },
+
+    /// Lists the version history of a template
+    #[command()]
+    Versions {
+        /// The template name or identifier to list the versions of
+        #[command(flatten)]
+        template_id_or_name: TemplateIdOrName,
+
+        /// Workers to count per version, as workers can't be listed
+        #[arg(short, long)]
+        worker_name: Vec<WorkerName>,
+    },
+
+    /// Reconstructs a WIT world from the exports of a template
+    #[command()]
+    Wit {
+        /// The template name or identifier to render
+        #[command(flatten)]
+        template_id_or_name: TemplateIdOrName,
+
+        /// The template version to render, defaults to the latest version
+        #[arg(long)]
+        version: Option<i32>,
+
+        /// The package name of the generated WIT (in namespace:name format)
+        #[arg(long)]
+        package_name: Option<String>,
+    },
+
+    /// Generates JSON schemas for the parameters and result of an exported function
+    #[command()]
+    Schema {
+        /// The template name or identifier the function belongs to
+        #[command(flatten)]
+        template_id_or_name: TemplateIdOrName,
+
+        /// The template version to use, defaults to the latest version
+        #[arg(long)]
+        version: Option<i32>,
+
+        /// Name of the exported function, as used for invocation
+        #[arg(short, long)]
+        function: String,
+    },
+
+    /// Watches a template WASM and uploads a new version every time it changes
+    #[command()]
+    Watch {
+        /// The template name or identifier to update
+        #[command(flatten)]
+        template_id_or_name: TemplateIdOrName,
+
+        /// The WASM file to be uploaded as new versions of the Golem template
+        #[arg(value_name = "template-file", value_hint = clap::ValueHint::FilePath)]
+        template_file: PathBuf,
+
+        /// Additional files or directories to watch, for example the template's sources
+        #[arg(long, value_hint = clap::ValueHint::AnyPath)]
+        watch: Vec<PathBuf>,
+
+        /// Shell command producing the WASM file, run before each upload
+        #[arg(short, long)]
+        build_command: Option<String>,
+
+        /// Workers to recreate on the new version after each upload. Their state is lost, and the
+        /// watch stops if one of them can't be created again
+        #[arg(short, long)]
+        worker_name: Vec<WorkerName>,
+
+        /// Milliseconds the files must stay unchanged before building and uploading
+        #[arg(long, default_value_t = 500)]
+        debounce: u64,
+    },
 }
 
 #[async_trait]
 pub trait TemplateHandler {
-    async fn handle(&self, subcommand: TemplateSubcommand) -> Result<GolemResult, GolemError>;
+    async fn handle(
+        &self,
+        output: &Output,
+        subcommand: TemplateSubcommand,
+    ) -> Result<GolemResult, GolemError>;
 
     async fn resolve_id(&self, reference: TemplateIdOrName) -> Result<RawTemplateId, GolemError>;
 }
 
-pub struct TemplateHandlerLive<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send>
-{
+pub struct TemplateHandlerLive<
+    'p,
+    C: TemplateClient + Send + Sync,
+    P: ProjectClient + Sync + Send,
+    W: WorkerClient + Sync + Send,
+    A: AccountClient + Sync + Send,
+> {
     pub client: C,
     pub projects: &'p P,
+    pub workers: W,
+    pub accounts: A,
+    pub account_id: AccountId,
+}
+
+impl<
+        'p,
+        C: TemplateClient + Send + Sync,
+        P: ProjectClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        A: AccountClient + Sync + Send,
+    > TemplateHandlerLive<'p, C, P, W, A>
+{
+    /// Warns when a new template would go over the plan's template limit. The check is best
+    /// effort, so failing to get the plan or the usage doesn't stop the command.
+    async fn warn_template_limit(&self) {
+        let check = async {
+            let plan = self.accounts.get_plan(&self.account_id).await?;
+            let usage = Usage::count(&self.account_id, self.projects, &self.client).await?;
+            Ok::<_, GolemError>((i64::from(plan.plan_data.template_limit), usage.templates))
+        };
+
+        match check.await {
+            Ok((limit, used)) if used >= limit => eprintln!(
+                "Warning: the account already has {used} templates, its plan allows {limit}"
+            ),
+            Ok(_) => {}
+            Err(GolemError { message: err, .. }) => {
+                info!("Skipping the template limit check: {err}")
+            }
+        }
+    }
+
+    async fn get_version(
+        &self,
+        id: &RawTemplateId,
+        version: Option<i32>,
+    ) -> Result<Template, GolemError> {
+        match version {
+            None => self.client.get_latest_metadata(id).await,
+            Some(version) => self.client.get_metadata(id, version).await,
+        }
+    }
 }
 
 #[async_trait]
-impl<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send> TemplateHandler
-    for TemplateHandlerLive<'p, C, P>
+impl<
+        'p,
+        C: TemplateClient + Send + Sync,
+        P: ProjectClient + Sync + Send,
+        W: WorkerClient + Sync + Send,
+        A: AccountClient + Sync + Send,
+    > TemplateHandler for TemplateHandlerLive<'p, C, P, W, A>
 {
-    async fn handle(&self, subcommand: TemplateSubcommand) -> Result<GolemResult, GolemError> {
+    async fn handle(
+        &self,
+        output: &Output,
+        subcommand: TemplateSubcommand,
+    ) -> Result<GolemResult, GolemError> {
         match subcommand {
             TemplateSubcommand::Add {
                 project_ref,
                 template_name,
                 template_file,
+                check_limit,
             } => {
                 let project_id = self.projects.resolve_id(project_ref).await?;
+                if check_limit {
+                    self.warn_template_limit().await;
+                }
+
                 let template = self
                     .client
                     .add(project_id, template_name, template_file)
@@ -119,6 +303,140 @@ impl<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send> Templa
 
                 Ok(GolemResult::Ok(Box::new(templates)))
             }
+            TemplateSubcommand::Diff {
+                template_id_or_name,
+                from,
+                to,
+                wasm,
+            } => {
+                let id = self.resolve_id(template_id_or_name).await?;
+
+                let to = match wasm {
+                    Some(_) => None,
+                    None => Some(self.get_version(&id, to).await?),
+                };
+
+                let from = match (from, &to) {
+                    (Some(version), _) => self.client.get_metadata(&id, version).await?,
+                    (None, Some(to)) => {
+                        let version = to.versioned_template_id.version;
+
+                        if version <= 0 {
+                            return Err(GolemError::new(format!(
+                                "Version {version} has no earlier version to compare with, use --from"
+                            )));
+                        }
+
+                        self.client.get_metadata(&id, version - 1).await?
+                    }
+                    (None, None) => self.client.get_latest_metadata(&id).await?,
+                };
+
+                let (to_str, to_exports) = match (to, wasm) {
+                    (Some(to), _) => (
+                        format!("version {}", to.versioned_template_id.version),
+                        to.metadata.exports,
+                    ),
+                    (None, Some(path)) => {
+                        (format!("{}", path.display()), exports_from_wasm(&path)?)
+                    }
+                    (None, None) => unreachable!("either a version or a WASM file is compared"),
+                };
+
+                let diff = TemplateDiff::new(
+                    format!("version {}", from.versioned_template_id.version),
+                    to_str,
+                    diff_exports(&from.metadata.exports, &to_exports),
+                );
+
+                if diff.breaking {
+                    diff.println(output)?;
+
+                    Err(GolemError::new(format!(
+                        "Breaking changes between {} and {}",
+                        diff.from, diff.to
+                    )))
+                } else {
+                    Ok(GolemResult::Ok(Box::new(diff)))
+                }
+            }
+            TemplateSubcommand::Versions {
+                template_id_or_name,
+                worker_name,
+            } => {
+                let id = self.resolve_id(template_id_or_name).await?;
+                let templates = self.client.get_versions(&id).await?;
+                let latest = templates
+                    .iter()
+                    .map(|t| t.versioned_template_id.version)
+                    .max();
+
+                let count_workers = !worker_name.is_empty();
+                let mut workers: HashMap<i32, usize> = HashMap::new();
+
+                for name in worker_name {
+                    let metadata = self.workers.get_metadata(name, id.clone()).await?;
+                    *workers.entry(metadata.template_version).or_default() += 1;
+                }
+
+                let versions: Vec<TemplateVersionView> = templates
+                    .iter()
+                    .sorted_by_key(|t| t.versioned_template_id.version)
+                    .map(|t| {
+                        let version = t.versioned_template_id.version;
+                        let mut view: TemplateVersionView = t.into();
+                        view.latest = Some(version) == latest;
+                        if count_workers {
+                            view.workers = Some(workers.get(&version).copied().unwrap_or(0));
+                        }
+                        view
+                    })
+                    .collect();
+
+                Ok(GolemResult::Ok(Box::new(versions)))
+            }
+            TemplateSubcommand::Wit {
+                template_id_or_name,
+                version,
+                package_name,
+            } => {
+                let id = self.resolve_id(template_id_or_name).await?;
+                let template = self.get_version(&id, version).await?;
+
+                Ok(GolemResult::Str(render_wit(&template, package_name)))
+            }
+            TemplateSubcommand::Schema {
+                template_id_or_name,
+                version,
+                function,
+            } => {
+                let id = self.resolve_id(template_id_or_name).await?;
+                let template = self.get_version(&id, version).await?;
+                let schema = function_schema(&template.metadata.exports, &function)?;
+
+                Ok(GolemResult::Json(schema))
+            }
+            TemplateSubcommand::Watch {
+                template_id_or_name,
+                template_file,
+                watch: watch_paths,
+                build_command,
+                worker_name,
+                debounce,
+            } => {
+                let id = self.resolve_id(template_id_or_name).await?;
+                let config = WatchConfig {
+                    template_file,
+                    watch: watch_paths,
+                    build_command,
+                    worker_names: worker_name,
+                    debounce: Duration::from_millis(debounce),
+                };
+
+                watch(&self.client, &self.workers, id, config, output).await?;
+
+                Ok(GolemResult::Str("Stopped watching".to_string()))
+            }
         }
     }
 
@@ -143,7 +461,7 @@ impl<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send> Templa
                         project_id.map_or("default".to_string(), |ProjectId(id)| id.to_string());
                     let template_name = name.0;
                     let ids: Vec<String> = templates.into_iter().map(|c| c.template_id).collect();
-                    Err(GolemError(formatdoc!(
+                    Err(GolemError::new(formatdoc!(
                         "
                         Multiple templates found for name {template_name} in project {project_str}:
                         {}
@@ -157,7 +475,7 @@ impl<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send> Templa
                             let project_str = project_id
                                 .map_or("default".to_string(), |ProjectId(id)| id.to_string());
                             let template_name = name.0;
-                            Err(GolemError(format!(
+                            Err(GolemError::new(format!(
                                 "Can't find template ${template_name} in {project_str}"
                             )))
                         }
@@ -166,9 +484,9 @@ impl<'p, C: TemplateClient + Send + Sync, P: ProjectClient + Sync + Send> Templa
 
                             match parsed {
                                 Ok(id) => Ok(RawTemplateId(id)),
-                                Err(err) => {
-                                    Err(GolemError(format!("Failed to parse template id: {err}")))
-                                }
+                                Err(err) => Err(GolemError::new(format!(
+                                    "Failed to parse template id: {err}"
+                                ))),
                             }
                         }
                     }
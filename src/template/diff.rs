@@ -0,0 +1,418 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+use golem_cloud_client::model::{
+    Export, ExportFunction, ExportInstance, FunctionParameter, FunctionResult, NameOptionTypePair,
+    NameTypePair, ResourceMode, Type, TypeBool, TypeChr, TypeEnum, TypeF32, TypeF64, TypeFlags,
+    TypeHandle, TypeList, TypeOption, TypeRecord, TypeResult, TypeS16, TypeS32, TypeS64, TypeS8,
+    TypeStr, TypeTuple, TypeU16, TypeU32, TypeU64, TypeU8, TypeVariant,
+};
+use golem_wasm_ast::analysis::{
+    AnalysedExport, AnalysedFunction, AnalysedResourceMode, AnalysedType, AnalysisContext,
+};
+use golem_wasm_ast::component::Component;
+use golem_wasm_ast::IgnoreAllButMetadata;
+use serde::Serialize;
+
+use crate::clients::template::show_exported_function;
+use crate::model::GolemError;
+use crate::table::TableColumns;
+use crate::template::wit::{function_kind, FunctionKind};
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ChangeKind {
+    Added,
+    Removed,
+    SignatureChanged,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FunctionChange {
+    pub function: String,
+    pub change: ChangeKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub old_signature: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub new_signature: Option<String>,
+}
+
+impl FunctionChange {
+    /// Only additions are safe for the callers of the old version
+    pub fn is_breaking(&self) -> bool {
+        self.change != ChangeKind::Added
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TemplateDiff {
+    pub from: String,
+    pub to: String,
+    pub breaking: bool,
+    pub changes: Vec<FunctionChange>,
+}
+
+impl TableColumns for TemplateDiff {}
+
+impl TemplateDiff {
+    pub fn new(from: String, to: String, changes: Vec<FunctionChange>) -> TemplateDiff {
+        TemplateDiff {
+            from,
+            to,
+            breaking: changes.iter().any(|c| c.is_breaking()),
+            changes,
+        }
+    }
+}
+
+fn flatten_exports(exports: &[Export]) -> BTreeMap<String, (String, &ExportFunction)> {
+    exports
+        .iter()
+        .flat_map(|exp| match exp {
+            Export::Instance(ExportInstance { name, functions }) => functions
+                .iter()
+                .map(|f| (format!("{name}/{}", f.name), (format!("{name}/"), f)))
+                .collect::<Vec<_>>(),
+            Export::Function(f) => vec![(f.name.clone(), ("".to_string(), f))],
+        })
+        .collect()
+}
+
+fn signature(prefix: &str, f: &ExportFunction) -> String {
+    show_exported_function(prefix, &f.name, &f.parameters, &f.results)
+}
+
+/// Resource names by handle id, qualified with the instance exporting them.
+fn resource_names(exports: &[Export]) -> HashMap<u64, String> {
+    let mut names = HashMap::new();
+
+    for (_, (prefix, f)) in flatten_exports(exports) {
+        let handle = match function_kind(&f.name) {
+            FunctionKind::Constructor(resource) => f.results.first().map(|r| (&r.typ, resource)),
+            FunctionKind::Method(resource, _) => f.parameters.first().map(|p| (&p.typ, resource)),
+            _ => None,
+        };
+
+        if let Some((Type::Handle(handle), resource)) = handle {
+            names.insert(handle.resource_id, format!("{prefix}{resource}"));
+        }
+    }
+
+    names
+}
+
+/// Resource ids are assigned when a template is analysed, so handles of two
+/// versions are matched by the name of their resource.
+struct Resources {
+    old: HashMap<u64, String>,
+    new: HashMap<u64, String>,
+}
+
+impl Resources {
+    fn same_type(&self, old: &Type, new: &Type) -> bool {
+        let same_opt = |old: &Option<Type>, new: &Option<Type>| match (old, new) {
+            (Some(old), Some(new)) => self.same_type(old, new),
+            (old, new) => old.is_none() && new.is_none(),
+        };
+
+        match (old, new) {
+            (Type::Handle(old), Type::Handle(new)) => {
+                old.mode == new.mode
+                    && match (
+                        self.old.get(&old.resource_id),
+                        self.new.get(&new.resource_id),
+                    ) {
+                        (Some(old_name), Some(new_name)) => old_name == new_name,
+                        _ => old.resource_id == new.resource_id,
+                    }
+            }
+            (Type::Option(old), Type::Option(new)) => self.same_type(&old.inner, &new.inner),
+            (Type::List(old), Type::List(new)) => self.same_type(&old.inner, &new.inner),
+            (Type::Result(old), Type::Result(new)) => {
+                same_opt(&old.ok, &new.ok) && same_opt(&old.err, &new.err)
+            }
+            (Type::Tuple(old), Type::Tuple(new)) => {
+                old.items.len() == new.items.len()
+                    && old
+                        .items
+                        .iter()
+                        .zip(&new.items)
+                        .all(|(old, new)| self.same_type(old, new))
+            }
+            (Type::Record(old), Type::Record(new)) => {
+                old.cases.len() == new.cases.len()
+                    && old.cases.iter().zip(&new.cases).all(|(old, new)| {
+                        old.name == new.name && self.same_type(&old.typ, &new.typ)
+                    })
+            }
+            (Type::Variant(old), Type::Variant(new)) => {
+                old.cases.len() == new.cases.len()
+                    && old
+                        .cases
+                        .iter()
+                        .zip(&new.cases)
+                        .all(|(old, new)| old.name == new.name && same_opt(&old.typ, &new.typ))
+            }
+            (old, new) => old == new,
+        }
+    }
+
+    fn same_types<'a>(
+        &self,
+        old: impl ExactSizeIterator<Item = &'a Type>,
+        new: impl ExactSizeIterator<Item = &'a Type>,
+    ) -> bool {
+        old.len() == new.len() && old.zip(new).all(|(old, new)| self.same_type(old, new))
+    }
+
+    fn same_signature(&self, old: &ExportFunction, new: &ExportFunction) -> bool {
+        self.same_types(
+            old.parameters.iter().map(|p| &p.typ),
+            new.parameters.iter().map(|p| &p.typ),
+        ) && self.same_types(
+            old.results.iter().map(|r| &r.typ),
+            new.results.iter().map(|r| &r.typ),
+        )
+    }
+}
+
+/// Compares two export trees function by function.
+///
+/// Parameter and result names are ignored, as invocations pass them positionally.
+pub fn diff_exports(from: &[Export], to: &[Export]) -> Vec<FunctionChange> {
+    let old = flatten_exports(from);
+    let new = flatten_exports(to);
+    let resources = Resources {
+        old: resource_names(from),
+        new: resource_names(to),
+    };
+
+    let mut changes = Vec::new();
+
+    for (name, (prefix, old_fn)) in &old {
+        match new.get(name) {
+            None => changes.push(FunctionChange {
+                function: name.clone(),
+                change: ChangeKind::Removed,
+                old_signature: Some(signature(prefix, old_fn)),
+                new_signature: None,
+            }),
+            Some((new_prefix, new_fn)) => {
+                if !resources.same_signature(old_fn, new_fn) {
+                    changes.push(FunctionChange {
+                        function: name.clone(),
+                        change: ChangeKind::SignatureChanged,
+                        old_signature: Some(signature(prefix, old_fn)),
+                        new_signature: Some(signature(new_prefix, new_fn)),
+                    })
+                }
+            }
+        }
+    }
+
+    for (name, (prefix, new_fn)) in &new {
+        if !old.contains_key(name) {
+            changes.push(FunctionChange {
+                function: name.clone(),
+                change: ChangeKind::Added,
+                old_signature: None,
+                new_signature: Some(signature(prefix, new_fn)),
+            })
+        }
+    }
+
+    changes.sort_by(|a, b| a.function.cmp(&b.function));
+
+    changes
+}
+
+/// Reads the exports of a local template WASM the same way the server analyses uploads.
+pub fn exports_from_wasm(path: &Path) -> Result<Vec<Export>, GolemError> {
+    let bytes = std::fs::read(path)
+        .map_err(|e| GolemError::new(format!("Can't read template file {path:?}: {e}")))?;
+    let component = Component::<IgnoreAllButMetadata>::from_bytes(&bytes)
+        .map_err(|e| GolemError::new(format!("Failed to parse template file {path:?}: {e}")))?;
+    let state = AnalysisContext::new(component);
+    let exports = state
+        .get_top_level_exports()
+        .map_err(|e| GolemError::new(format!("Failed to analyse template exports: {e:?}")))?;
+
+    Ok(exports.into_iter().map(export_from_analysed).collect())
+}
+
+fn export_from_analysed(export: AnalysedExport) -> Export {
+    match export {
+        AnalysedExport::Function(f) => Export::Function(function_from_analysed(f)),
+        AnalysedExport::Instance(instance) => Export::Instance(ExportInstance {
+            name: instance.name,
+            functions: instance
+                .funcs
+                .into_iter()
+                .map(function_from_analysed)
+                .collect(),
+        }),
+    }
+}
+
+fn function_from_analysed(f: AnalysedFunction) -> ExportFunction {
+    ExportFunction {
+        name: f.name,
+        parameters: f
+            .params
+            .into_iter()
+            .map(|p| FunctionParameter {
+                name: p.name,
+                typ: type_from_analysed(p.typ),
+            })
+            .collect(),
+        results: f
+            .results
+            .into_iter()
+            .map(|r| FunctionResult {
+                name: r.name,
+                typ: type_from_analysed(r.typ),
+            })
+            .collect(),
+    }
+}
+
+fn type_from_analysed(typ: AnalysedType) -> Type {
+    match typ {
+        AnalysedType::Bool => Type::Bool(TypeBool {}),
+        AnalysedType::S8 => Type::S8(TypeS8 {}),
+        AnalysedType::U8 => Type::U8(TypeU8 {}),
+        AnalysedType::S16 => Type::S16(TypeS16 {}),
+        AnalysedType::U16 => Type::U16(TypeU16 {}),
+        AnalysedType::S32 => Type::S32(TypeS32 {}),
+        AnalysedType::U32 => Type::U32(TypeU32 {}),
+        AnalysedType::S64 => Type::S64(TypeS64 {}),
+        AnalysedType::U64 => Type::U64(TypeU64 {}),
+        AnalysedType::F32 => Type::F32(TypeF32 {}),
+        AnalysedType::F64 => Type::F64(TypeF64 {}),
+        AnalysedType::Chr => Type::Chr(TypeChr {}),
+        AnalysedType::Str => Type::Str(TypeStr {}),
+        AnalysedType::List(inner) => Type::List(Box::new(TypeList {
+            inner: type_from_analysed(*inner),
+        })),
+        AnalysedType::Tuple(items) => Type::Tuple(TypeTuple {
+            items: items.into_iter().map(type_from_analysed).collect(),
+        }),
+        AnalysedType::Record(fields) => Type::Record(TypeRecord {
+            cases: fields
+                .into_iter()
+                .map(|(name, typ)| NameTypePair {
+                    name,
+                    typ: type_from_analysed(typ),
+                })
+                .collect(),
+        }),
+        AnalysedType::Flags(cases) => Type::Flags(TypeFlags { cases }),
+        AnalysedType::Enum(cases) => Type::Enum(TypeEnum { cases }),
+        AnalysedType::Option(inner) => Type::Option(Box::new(TypeOption {
+            inner: type_from_analysed(*inner),
+        })),
+        AnalysedType::Result { ok, error } => Type::Result(Box::new(TypeResult {
+            ok: ok.map(|t| type_from_analysed(*t)),
+            err: error.map(|t| type_from_analysed(*t)),
+        })),
+        AnalysedType::Variant(cases) => Type::Variant(TypeVariant {
+            cases: cases
+                .into_iter()
+                .map(|(name, typ)| NameOptionTypePair {
+                    name,
+                    typ: typ.map(type_from_analysed),
+                })
+                .collect(),
+        }),
+        AnalysedType::Resource { id, resource_mode } => Type::Handle(TypeHandle {
+            resource_id: id.value,
+            mode: match resource_mode {
+                AnalysedResourceMode::Owned => ResourceMode::Owned,
+                AnalysedResourceMode::Borrowed => ResourceMode::Borrowed,
+            },
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use golem_cloud_client::model::{
+        Export, ExportFunction, ExportInstance, FunctionParameter, FunctionResult, ResourceMode,
+        Type, TypeHandle, TypeU64,
+    };
+
+    use crate::template::diff::{diff_exports, ChangeKind};
+
+    fn handle(resource_id: u64, mode: ResourceMode) -> Type {
+        Type::Handle(TypeHandle { resource_id, mode })
+    }
+
+    /// Resources `a` and `b` with the given ids, and a function taking the handle `used`
+    fn api(a: u64, b: u64, used: u64) -> Vec<Export> {
+        let function = |name: &str, parameters: Vec<Type>, results: Vec<Type>| ExportFunction {
+            name: name.to_string(),
+            parameters: parameters
+                .into_iter()
+                .map(|typ| FunctionParameter {
+                    name: "handle".to_string(),
+                    typ,
+                })
+                .collect(),
+            results: results
+                .into_iter()
+                .map(|typ| FunctionResult { name: None, typ })
+                .collect(),
+        };
+
+        vec![Export::Instance(ExportInstance {
+            name: "golem:it/api".to_string(),
+            functions: vec![
+                function(
+                    "[constructor]a",
+                    vec![],
+                    vec![handle(a, ResourceMode::Owned)],
+                ),
+                function(
+                    "[constructor]b",
+                    vec![],
+                    vec![handle(b, ResourceMode::Owned)],
+                ),
+                function(
+                    "consume",
+                    vec![handle(used, ResourceMode::Owned)],
+                    vec![Type::U64(TypeU64 {})],
+                ),
+            ],
+        })]
+    }
+
+    #[test]
+    fn handles_are_compared_by_resource_name() {
+        assert_eq!(diff_exports(&api(1, 2, 1), &api(2, 1, 2)), vec![]);
+    }
+
+    #[test]
+    fn handles_of_another_resource_change_the_signature() {
+        let changes = diff_exports(&api(1, 2, 1), &api(2, 1, 1));
+
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].function, "golem:it/api/consume");
+        assert_eq!(changes[0].change, ChangeKind::SignatureChanged);
+    }
+}
@@ -0,0 +1,177 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use golem_cloud_client::model::{
+    Export, ExportFunction, ExportInstance, NameOptionTypePair, NameTypePair, Type, TypeEnum,
+    TypeFlags, TypeRecord, TypeTuple, TypeVariant,
+};
+use serde_json::{json, Map, Value};
+
+use crate::model::GolemError;
+
+const SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+fn integer(min: Value, max: Value) -> Value {
+    json!({ "type": "integer", "minimum": min, "maximum": max })
+}
+
+fn single_key_object(key: &str, value: Value) -> Value {
+    json!({
+        "type": "object",
+        "properties": { key: value },
+        "required": [key],
+        "additionalProperties": false
+    })
+}
+
+fn fixed_array(items: Vec<Value>) -> Value {
+    let len = items.len();
+
+    json!({
+        "type": "array",
+        "prefixItems": items,
+        "items": false,
+        "minItems": len,
+        "maxItems": len
+    })
+}
+
+/// Schema of a single value in the JSON encoding used for invocation parameters and results.
+pub fn type_schema(typ: &Type) -> Value {
+    match typ {
+        Type::Variant(TypeVariant { cases }) => {
+            let cases: Vec<Value> = cases
+                .iter()
+                .map(|NameOptionTypePair { name, typ }| {
+                    let value = typ.as_ref().map_or(json!({ "type": "null" }), type_schema);
+                    single_key_object(name, value)
+                })
+                .collect();
+            json!({ "oneOf": cases })
+        }
+        Type::Result(boxed) => {
+            let ok = boxed
+                .ok
+                .as_ref()
+                .map_or(json!({ "type": "null" }), type_schema);
+            let err = boxed
+                .err
+                .as_ref()
+                .map_or(json!({ "type": "null" }), type_schema);
+            json!({ "oneOf": [single_key_object("ok", ok), single_key_object("err", err)] })
+        }
+        Type::Option(boxed) => json!({ "anyOf": [type_schema(&boxed.inner), { "type": "null" }] }),
+        Type::Enum(TypeEnum { cases }) => json!({ "type": "string", "enum": cases }),
+        Type::Flags(TypeFlags { cases }) => json!({
+            "type": "array",
+            "items": { "type": "string", "enum": cases },
+            "uniqueItems": true
+        }),
+        Type::Record(TypeRecord { cases }) => {
+            let mut properties = Map::new();
+            let mut required = Vec::new();
+
+            for NameTypePair { name, typ } in cases {
+                properties.insert(name.clone(), type_schema(typ));
+
+                // Missing optional fields are accepted as none
+                if !matches!(typ, Type::Option(_)) {
+                    required.push(name.clone());
+                }
+            }
+
+            json!({
+                "type": "object",
+                "properties": properties,
+                "required": required,
+                "additionalProperties": false
+            })
+        }
+        Type::Tuple(TypeTuple { items }) => fixed_array(items.iter().map(type_schema).collect()),
+        Type::List(boxed) => json!({ "type": "array", "items": type_schema(&boxed.inner) }),
+        Type::Str(_) => json!({ "type": "string" }),
+        Type::Chr(_) => {
+            let mut schema = integer(json!(0), json!(0x10FFFF));
+            schema["description"] = json!("Unicode code point");
+            schema
+        }
+        Type::F64(_) => json!({ "type": "number" }),
+        Type::F32(_) => json!({ "type": "number", "minimum": f32::MIN, "maximum": f32::MAX }),
+        Type::U64(_) => integer(json!(u64::MIN), json!(u64::MAX)),
+        Type::S64(_) => integer(json!(i64::MIN), json!(i64::MAX)),
+        Type::U32(_) => integer(json!(u32::MIN), json!(u32::MAX)),
+        Type::S32(_) => integer(json!(i32::MIN), json!(i32::MAX)),
+        Type::U16(_) => integer(json!(u16::MIN), json!(u16::MAX)),
+        Type::S16(_) => integer(json!(i16::MIN), json!(i16::MAX)),
+        Type::U8(_) => integer(json!(u8::MIN), json!(u8::MAX)),
+        Type::S8(_) => integer(json!(i8::MIN), json!(i8::MAX)),
+        Type::Bool(_) => json!({ "type": "boolean" }),
+        Type::Handle(_) => json!({
+            "type": "string",
+            "pattern": "^.+/[0-9]+$",
+            "description": "Resource handle in worker-url/resource-id format"
+        }),
+    }
+}
+
+fn find_function<'a>(exports: &'a [Export], function: &str) -> Option<&'a ExportFunction> {
+    exports.iter().find_map(|exp| match exp {
+        Export::Instance(ExportInstance { name, functions }) => function
+            .strip_prefix(&format!("{name}/"))
+            .and_then(|f| functions.iter().find(|candidate| candidate.name == f)),
+        Export::Function(f) => Some(f).filter(|f| f.name == function),
+    })
+}
+
+/// Generates JSON schemas for the parameter array and the result array of an exported function.
+pub fn function_schema(exports: &[Export], function: &str) -> Result<Value, GolemError> {
+    let f = find_function(exports, function)
+        .ok_or_else(|| GolemError::new(format!("Can't find exported function {function}")))?;
+
+    let parameters: Vec<Value> = f
+        .parameters
+        .iter()
+        .map(|p| {
+            let mut schema = type_schema(&p.typ);
+            schema["title"] = json!(p.name);
+            schema
+        })
+        .collect();
+
+    let results: Vec<Value> = f
+        .results
+        .iter()
+        .map(|r| {
+            let mut schema = type_schema(&r.typ);
+            if let Some(name) = &r.name {
+                schema["title"] = json!(name);
+            }
+            schema
+        })
+        .collect();
+
+    let mut parameters = fixed_array(parameters);
+    parameters["$schema"] = json!(SCHEMA_DIALECT);
+    parameters["title"] = json!(format!("{function} parameters"));
+
+    let mut result = fixed_array(results);
+    result["$schema"] = json!(SCHEMA_DIALECT);
+    result["title"] = json!(format!("{function} result"));
+
+    Ok(json!({
+        "function": function,
+        "parameters": parameters,
+        "result": result
+    }))
+}
@@ -0,0 +1,257 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use tokio::process::Command;
+use tokio::time;
+use tracing::{error, info};
+
+use crate::clients::template::TemplateClient;
+use crate::clients::worker::WorkerClient;
+use crate::model::{GolemError, Output, PathBufOrStdin, PrintRes, RawTemplateId, WorkerName};
+
+pub struct WatchConfig {
+    pub template_file: PathBuf,
+    pub watch: Vec<PathBuf>,
+    pub build_command: Option<String>,
+    pub worker_names: Vec<WorkerName>,
+    pub debounce: Duration,
+}
+
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+// Polling instead of file system events, as build tools usually replace the output file,
+// which silently ends inode based watches.
+fn collect(path: &Path, snapshot: &mut Snapshot) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return;
+    };
+
+    if metadata.is_dir() {
+        if let Ok(entries) = std::fs::read_dir(path) {
+            for entry in entries.flatten() {
+                collect(&entry.path(), snapshot);
+            }
+        }
+    } else {
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+    }
+}
+
+fn snapshot(paths: &[PathBuf]) -> Snapshot {
+    let mut snapshot = BTreeMap::new();
+
+    for path in paths {
+        collect(path, &mut snapshot);
+    }
+
+    snapshot
+}
+
+fn file_snapshot(path: &Path) -> Snapshot {
+    let mut snapshot = BTreeMap::new();
+    collect(path, &mut snapshot);
+    snapshot
+}
+
+async fn build(command: &str) -> Result<(), GolemError> {
+    info!("Running build command: {command}");
+
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+
+    let status = cmd
+        .arg(command)
+        .status()
+        .await
+        .map_err(|e| GolemError::new(format!("Failed to run build command: {e}")))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(GolemError::new(format!(
+            "Build command failed with {status}"
+        )))
+    }
+}
+
+/// Failures of a redeploy round. Most are retried on the next change, but a worker that was
+/// deleted and could not be created again stops the watch, so it doesn't go unnoticed.
+enum RedeployError {
+    Retry(GolemError),
+    WorkerLost(GolemError),
+}
+
+impl From<GolemError> for RedeployError {
+    fn from(err: GolemError) -> Self {
+        RedeployError::Retry(err)
+    }
+}
+
+/// Workers keep running the version they were created with, so they are recreated with their
+/// original arguments and environment to pick up the new version.
+async fn recreate_worker<W: WorkerClient + Sync + Send>(
+    workers: &W,
+    template_id: &RawTemplateId,
+    name: &WorkerName,
+) -> Result<(), RedeployError> {
+    let metadata = workers
+        .get_metadata(name.clone(), template_id.clone())
+        .await?;
+
+    workers.delete(name.clone(), template_id.clone()).await?;
+
+    let env: Vec<(String, String)> = metadata.env.into_iter().collect();
+
+    let worker_id = workers
+        .new_worker(
+            name.clone(),
+            template_id.clone(),
+            metadata.args.clone(),
+            env.clone(),
+        )
+        .await
+        .map_err(|err| {
+            let env_args = env.iter().map(|(k, v)| format!(" --env {k:?}={v:?}"));
+            let args = metadata.args.iter().map(|arg| format!(" {arg:?}"));
+
+            RedeployError::WorkerLost(GolemError::new(format!(
+                "Worker {name} was deleted but could not be created again: {}\n\
+                 Create it again with: worker add --template-id {} --worker-name {name}{} --{}",
+                err.message,
+                template_id.0,
+                env_args.collect::<String>(),
+                args.collect::<String>(),
+            )))
+        })?;
+
+    info!(
+        "Recreated worker {} on version {}",
+        name, worker_id.template_version_used
+    );
+
+    Ok(())
+}
+
+async fn redeploy<C: TemplateClient + Sync + Send, W: WorkerClient + Sync + Send>(
+    client: &C,
+    workers: &W,
+    template_id: &RawTemplateId,
+    config: &WatchConfig,
+    output: &Output,
+    uploaded: &mut Snapshot,
+) -> Result<(), RedeployError> {
+    if let Some(command) = &config.build_command {
+        build(command).await?;
+    }
+
+    let current = file_snapshot(&config.template_file);
+
+    if current.is_empty() {
+        return Err(GolemError::new(format!(
+            "Template file {} does not exist",
+            config.template_file.display()
+        ))
+        .into());
+    }
+
+    if current == *uploaded {
+        info!("Template file unchanged, skipping upload");
+        return Ok(());
+    }
+
+    let template = client
+        .update(
+            template_id.clone(),
+            PathBufOrStdin::Path(config.template_file.clone()),
+        )
+        .await?;
+    *uploaded = current;
+
+    template.println(output)?;
+
+    for name in &config.worker_names {
+        recreate_worker(workers, template_id, name).await?;
+    }
+
+    Ok(())
+}
+
+/// Uploads a new template version every time the watched files change, until interrupted or a
+/// recreated worker is lost.
+pub async fn watch<C: TemplateClient + Sync + Send, W: WorkerClient + Sync + Send>(
+    client: &C,
+    workers: &W,
+    template_id: RawTemplateId,
+    config: WatchConfig,
+    output: &Output,
+) -> Result<(), GolemError> {
+    let mut paths = config.watch.clone();
+    paths.push(config.template_file.clone());
+
+    let mut uploaded = file_snapshot(&config.template_file);
+    let mut last = snapshot(&paths);
+
+    info!("Watching {paths:?} for changes");
+
+    loop {
+        time::sleep(config.debounce).await;
+
+        let mut current = snapshot(&paths);
+        if current == last {
+            continue;
+        }
+
+        // Wait until the files settle, so a half written file is never uploaded
+        loop {
+            time::sleep(config.debounce).await;
+            let next = snapshot(&paths);
+            if next == current {
+                break;
+            }
+            current = next;
+        }
+
+        match redeploy(
+            client,
+            workers,
+            &template_id,
+            &config,
+            output,
+            &mut uploaded,
+        )
+        .await
+        {
+            Ok(()) => {}
+            Err(RedeployError::Retry(err)) => error!("{err}"),
+            Err(RedeployError::WorkerLost(err)) => return Err(err),
+        }
+
+        // Changes made by the build itself must not trigger another round
+        last = snapshot(&paths);
+    }
+}
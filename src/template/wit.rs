@@ -0,0 +1,647 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use std::collections::{HashMap, HashSet};
+
+use golem_cloud_client::model::{
+    Export, ExportFunction, ExportInstance, FunctionResult, NameOptionTypePair, NameTypePair,
+    ResourceMode, Template, Type, TypeEnum, TypeFlags, TypeRecord, TypeTuple, TypeVariant,
+};
+use itertools::Itertools;
+
+const KEYWORDS: &[&str] = &[
+    "as",
+    "bool",
+    "borrow",
+    "char",
+    "constructor",
+    "enum",
+    "export",
+    "f32",
+    "f64",
+    "flags",
+    "float32",
+    "float64",
+    "func",
+    "import",
+    "include",
+    "interface",
+    "list",
+    "option",
+    "own",
+    "package",
+    "record",
+    "resource",
+    "result",
+    "s16",
+    "s32",
+    "s64",
+    "s8",
+    "static",
+    "string",
+    "tuple",
+    "type",
+    "u16",
+    "u32",
+    "u64",
+    "u8",
+    "use",
+    "variant",
+    "with",
+    "world",
+];
+
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("%{name}")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Turns an arbitrary name into a kebab-case WIT identifier.
+///
+/// Every word of a WIT identifier has to start with a letter, so words starting with a digit
+/// get an `x` prefix.
+fn kebab(name: &str) -> String {
+    let words: Vec<String> = name
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| {
+            let word = word.to_ascii_lowercase();
+            if word.starts_with(|c: char| c.is_ascii_digit()) {
+                format!("x{word}")
+            } else {
+                word
+            }
+        })
+        .collect();
+
+    if words.is_empty() {
+        "unnamed".to_string()
+    } else {
+        words.join("-")
+    }
+}
+
+/// Adds a suffix to a name until it is unused, keeping the suffix a valid WIT word.
+fn unique(base: String, used: &HashSet<String>) -> String {
+    let mut name = base.clone();
+    let mut idx = 2;
+
+    while used.contains(&name) {
+        name = format!("{base}-v{idx}");
+        idx += 1;
+    }
+
+    name
+}
+
+pub(super) enum FunctionKind<'a> {
+    Freestanding,
+    Constructor(&'a str),
+    Method(&'a str, &'a str),
+    Static(&'a str, &'a str),
+}
+
+pub(super) fn function_kind(name: &str) -> FunctionKind<'_> {
+    if let Some(resource) = name.strip_prefix("[constructor]") {
+        FunctionKind::Constructor(resource)
+    } else if let Some((resource, method)) = name
+        .strip_prefix("[method]")
+        .and_then(|rest| rest.split_once('.'))
+    {
+        FunctionKind::Method(resource, method)
+    } else if let Some((resource, method)) = name
+        .strip_prefix("[static]")
+        .and_then(|rest| rest.split_once('.'))
+    {
+        FunctionKind::Static(resource, method)
+    } else {
+        FunctionKind::Freestanding
+    }
+}
+
+/// Named type definitions synthesised for a single interface or world.
+struct Scope {
+    used: HashSet<String>,
+    defs: Vec<(Type, String, String)>,
+    resources: HashMap<u64, String>,
+}
+
+impl Scope {
+    fn new(functions: &[ExportFunction]) -> Scope {
+        let mut resources = HashMap::new();
+
+        for f in functions {
+            let handle = match function_kind(&f.name) {
+                FunctionKind::Constructor(resource) => {
+                    f.results.first().map(|r| (&r.typ, resource))
+                }
+                FunctionKind::Method(resource, _) => {
+                    f.parameters.first().map(|p| (&p.typ, resource))
+                }
+                _ => None,
+            };
+
+            if let Some((Type::Handle(handle), resource)) = handle {
+                resources.insert(handle.resource_id, kebab(resource));
+            }
+        }
+
+        let mut used: HashSet<String> = resources.values().cloned().collect();
+        used.extend(functions.iter().map(|f| kebab(&f.name)));
+
+        Scope {
+            used,
+            defs: Vec::new(),
+            resources,
+        }
+    }
+
+    fn fresh(&mut self, hint: &str) -> String {
+        let name = unique(kebab(hint), &self.used);
+
+        self.used.insert(name.clone());
+        name
+    }
+
+    fn resource(&mut self, id: u64) -> String {
+        if let Some(name) = self.resources.get(&id) {
+            name.clone()
+        } else {
+            let name = self.fresh(&format!("resource-{id}"));
+            self.resources.insert(id, name.clone());
+            name
+        }
+    }
+
+    fn define(&mut self, typ: &Type, hint: &str) -> String {
+        if let Some((_, name, _)) = self.defs.iter().find(|(t, _, _)| t == typ) {
+            return ident(name);
+        }
+
+        let name = self.fresh(hint);
+
+        let body = match typ {
+            Type::Record(TypeRecord { cases }) => {
+                let fields: Vec<String> = cases
+                    .iter()
+                    .map(|NameTypePair { name: field, typ }| {
+                        let typ = self.render(typ, &format!("{name}-{field}"));
+                        format!("    {}: {typ},", ident(field))
+                    })
+                    .collect();
+                format!("record {} {{\n{}\n}}", ident(&name), fields.join("\n"))
+            }
+            Type::Variant(TypeVariant { cases }) => {
+                let cases: Vec<String> = cases
+                    .iter()
+                    .map(|NameOptionTypePair { name: case, typ }| match typ {
+                        None => format!("    {},", ident(case)),
+                        Some(typ) => {
+                            let typ = self.render(typ, &format!("{name}-{case}"));
+                            format!("    {}({typ}),", ident(case))
+                        }
+                    })
+                    .collect();
+                format!("variant {} {{\n{}\n}}", ident(&name), cases.join("\n"))
+            }
+            Type::Enum(TypeEnum { cases }) => {
+                let cases: Vec<String> =
+                    cases.iter().map(|c| format!("    {},", ident(c))).collect();
+                format!("enum {} {{\n{}\n}}", ident(&name), cases.join("\n"))
+            }
+            Type::Flags(TypeFlags { cases }) => {
+                let cases: Vec<String> =
+                    cases.iter().map(|c| format!("    {},", ident(c))).collect();
+                format!("flags {} {{\n{}\n}}", ident(&name), cases.join("\n"))
+            }
+            _ => unreachable!("only nominal types are defined"),
+        };
+
+        self.defs.push((typ.clone(), name.clone(), body));
+
+        ident(&name)
+    }
+
+    fn render(&mut self, typ: &Type, hint: &str) -> String {
+        match typ {
+            Type::Record(_) | Type::Variant(_) | Type::Enum(_) | Type::Flags(_) => {
+                self.define(typ, hint)
+            }
+            Type::Result(boxed) => match (&boxed.ok, &boxed.err) {
+                (None, None) => "result".to_string(),
+                (Some(ok), None) => format!("result<{}>", self.render(ok, &format!("{hint}-ok"))),
+                (None, Some(err)) => {
+                    format!("result<_, {}>", self.render(err, &format!("{hint}-error")))
+                }
+                (Some(ok), Some(err)) => format!(
+                    "result<{}, {}>",
+                    self.render(ok, &format!("{hint}-ok")),
+                    self.render(err, &format!("{hint}-error"))
+                ),
+            },
+            Type::Option(boxed) => format!("option<{}>", self.render(&boxed.inner, hint)),
+            Type::Tuple(TypeTuple { items }) => {
+                let items: Vec<String> = items
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, typ)| self.render(typ, &format!("{hint}-{idx}")))
+                    .collect();
+                format!("tuple<{}>", items.join(", "))
+            }
+            Type::List(boxed) => {
+                format!(
+                    "list<{}>",
+                    self.render(&boxed.inner, &format!("{hint}-item"))
+                )
+            }
+            Type::Str(_) => "string".to_string(),
+            Type::Chr(_) => "char".to_string(),
+            Type::F64(_) => "f64".to_string(),
+            Type::F32(_) => "f32".to_string(),
+            Type::U64(_) => "u64".to_string(),
+            Type::S64(_) => "s64".to_string(),
+            Type::U32(_) => "u32".to_string(),
+            Type::S32(_) => "s32".to_string(),
+            Type::U16(_) => "u16".to_string(),
+            Type::S16(_) => "s16".to_string(),
+            Type::U8(_) => "u8".to_string(),
+            Type::S8(_) => "s8".to_string(),
+            Type::Bool(_) => "bool".to_string(),
+            Type::Handle(handle) => {
+                let resource = ident(&self.resource(handle.resource_id));
+                match handle.mode {
+                    ResourceMode::Borrowed => format!("borrow<{resource}>"),
+                    ResourceMode::Owned => resource,
+                }
+            }
+        }
+    }
+
+    fn results(&mut self, results: &[FunctionResult], hint: &str) -> String {
+        match results {
+            [] => "".to_string(),
+            [FunctionResult { name: None, typ }] => {
+                format!(" -> {}", self.render(typ, &format!("{hint}-result")))
+            }
+            _ => {
+                let results: Vec<String> = results
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, r)| {
+                        let name = r.name.clone().unwrap_or(format!("result{idx}"));
+                        let typ = self.render(&r.typ, &format!("{hint}-{name}"));
+                        format!("{}: {typ}", ident(&kebab(&name)))
+                    })
+                    .collect();
+                format!(" -> ({})", results.join(", "))
+            }
+        }
+    }
+
+    fn signature(&mut self, f: &ExportFunction, skip_self: bool, hint: &str) -> String {
+        let skip = usize::from(skip_self);
+        let params: Vec<String> = f
+            .parameters
+            .iter()
+            .skip(skip)
+            .map(|p| {
+                let typ = self.render(&p.typ, &format!("{hint}-{}", p.name));
+                format!("{}: {typ}", ident(&kebab(&p.name)))
+            })
+            .collect();
+        let results = self.results(&f.results, hint);
+
+        format!("({}){results}", params.join(", "))
+    }
+}
+
+fn render_interface(name: &str, functions: &[ExportFunction]) -> String {
+    let mut scope = Scope::new(functions);
+    let mut resource_items: Vec<(String, Vec<String>)> = Vec::new();
+    let mut items: Vec<String> = Vec::new();
+
+    for f in functions {
+        match function_kind(&f.name) {
+            FunctionKind::Freestanding => {
+                let sig = scope.signature(f, false, &f.name);
+                items.push(format!("    {}: func{sig};", ident(&kebab(&f.name))));
+            }
+            FunctionKind::Constructor(resource) => {
+                let resource = kebab(resource);
+                let params: Vec<String> = f
+                    .parameters
+                    .iter()
+                    .map(|p| {
+                        let typ = scope.render(&p.typ, &format!("{resource}-{}", p.name));
+                        format!("{}: {typ}", ident(&kebab(&p.name)))
+                    })
+                    .collect();
+                let item = format!("constructor({});", params.join(", "));
+                push_resource_item(&mut resource_items, resource, item);
+            }
+            FunctionKind::Method(resource, method) => {
+                let resource = kebab(resource);
+                let sig = scope.signature(f, true, &format!("{resource}-{method}"));
+                let item = format!("{}: func{sig};", ident(&kebab(method)));
+                push_resource_item(&mut resource_items, resource, item);
+            }
+            FunctionKind::Static(resource, method) => {
+                let resource = kebab(resource);
+                let sig = scope.signature(f, false, &format!("{resource}-{method}"));
+                let item = format!("{}: static func{sig};", ident(&kebab(method)));
+                push_resource_item(&mut resource_items, resource, item);
+            }
+        }
+    }
+
+    // Handles whose resource has no constructor or method in this interface
+    for resource in scope.resources.values() {
+        if !resource_items.iter().any(|(name, _)| name == resource) {
+            resource_items.push((resource.clone(), Vec::new()));
+        }
+    }
+    resource_items.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let mut body: Vec<String> = scope.defs.iter().map(|(_, _, def)| indent(def)).collect();
+
+    for (resource, resource_items) in resource_items {
+        if resource_items.is_empty() {
+            body.push(format!("    resource {};", ident(&resource)));
+        } else {
+            let members: Vec<String> = resource_items
+                .iter()
+                .map(|item| format!("        {item}"))
+                .collect();
+            body.push(format!(
+                "    resource {} {{\n{}\n    }}",
+                ident(&resource),
+                members.join("\n")
+            ));
+        }
+    }
+
+    body.extend(items);
+
+    format!("interface {} {{\n{}\n}}", ident(name), body.join("\n\n"))
+}
+
+fn push_resource_item(items: &mut Vec<(String, Vec<String>)>, resource: String, item: String) {
+    match items.iter_mut().find(|(name, _)| *name == resource) {
+        Some((_, members)) => members.push(item),
+        None => items.push((resource, vec![item])),
+    }
+}
+
+fn indent(def: &str) -> String {
+    def.lines()
+        .map(|line| format!("    {line}"))
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Splits `ns:pkg/iface@version` into its package and interface parts.
+fn split_instance_name(name: &str) -> (Option<&str>, &str) {
+    let name = name.split_once('@').map_or(name, |(name, _)| name);
+
+    match name.split_once('/') {
+        Some((package, interface)) => (Some(package), interface),
+        None => (None, name),
+    }
+}
+
+/// Reconstructs a WIT package with a single world from the template's exports.
+///
+/// Every exported instance becomes a local interface of the package, and types which are
+/// anonymous in the metadata get names derived from where they are used.
+pub fn render_wit(template: &Template, package_name: Option<String>) -> String {
+    let exports = &template.metadata.exports;
+
+    let package_name = package_name
+        .or_else(|| {
+            exports.iter().find_map(|exp| match exp {
+                Export::Instance(ExportInstance { name, .. }) => {
+                    split_instance_name(name).0.map(|p| p.to_string())
+                }
+                Export::Function(_) => None,
+            })
+        })
+        .unwrap_or("golem:template".to_string());
+
+    let mut interface_names: HashSet<String> = HashSet::new();
+    let mut sections: Vec<String> = vec![format!("package {package_name};")];
+    let mut world_exports: Vec<String> = Vec::new();
+    let mut world_functions: Vec<ExportFunction> = Vec::new();
+
+    for exp in exports {
+        match exp {
+            Export::Instance(ExportInstance { name, functions }) => {
+                let interface = unique(kebab(split_instance_name(name).1), &interface_names);
+                interface_names.insert(interface.clone());
+
+                sections.push(render_interface(&interface, functions));
+                world_exports.push(format!("    export {};", ident(&interface)));
+            }
+            Export::Function(f) => world_functions.push(f.clone()),
+        }
+    }
+
+    let mut world_body: Vec<String> = Vec::new();
+
+    if !world_functions.is_empty() {
+        let mut scope = Scope::new(&world_functions);
+        let functions: Vec<String> = world_functions
+            .iter()
+            .map(|f| {
+                let sig = scope.signature(f, false, &f.name);
+                format!("    export {}: func{sig};", ident(&kebab(&f.name)))
+            })
+            .collect();
+
+        world_body.extend(scope.defs.iter().map(|(_, _, def)| indent(def)));
+        world_body.extend(
+            scope
+                .resources
+                .values()
+                .sorted()
+                .map(|resource| format!("    resource {};", ident(resource))),
+        );
+        world_body.push(functions.join("\n"));
+    }
+
+    if !world_exports.is_empty() {
+        world_body.push(world_exports.join("\n"));
+    }
+
+    sections.push(format!(
+        "world {} {{\n{}\n}}",
+        ident(&kebab(&template.template_name)),
+        world_body.join("\n\n")
+    ));
+
+    sections.join("\n\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use golem_cloud_client::model::{
+        Export, ExportFunction, ExportInstance, FunctionParameter, FunctionResult, NameTypePair,
+        ProtectedTemplateId, Template, TemplateMetadata, Type, TypeRecord, TypeTuple, TypeU32,
+        UserTemplateId, VersionedTemplateId,
+    };
+    use uuid::Uuid;
+
+    use crate::template::wit::{kebab, render_wit};
+
+    fn record(fields: Vec<(&str, Type)>) -> Type {
+        Type::Record(TypeRecord {
+            cases: fields
+                .into_iter()
+                .map(|(name, typ)| NameTypePair {
+                    name: name.to_string(),
+                    typ,
+                })
+                .collect(),
+        })
+    }
+
+    fn u32() -> Type {
+        Type::U32(TypeU32 {})
+    }
+
+    fn template(name: &str, exports: Vec<Export>) -> Template {
+        let id = VersionedTemplateId {
+            template_id: Uuid::nil(),
+            version: 0,
+        };
+
+        Template {
+            versioned_template_id: id.clone(),
+            user_template_id: UserTemplateId {
+                versioned_template_id: id.clone(),
+            },
+            protected_template_id: ProtectedTemplateId {
+                versioned_template_id: id,
+            },
+            template_name: name.to_string(),
+            template_size: 0,
+            metadata: TemplateMetadata {
+                exports,
+                producers: Vec::new(),
+            },
+            project_id: Uuid::nil(),
+        }
+    }
+
+    fn assert_parses(wit: &str) {
+        if let Err(err) = wit_parser::UnresolvedPackage::parse(Path::new("test.wit"), wit) {
+            panic!("Rendered WIT doesn't parse: {err:?}\n{wit}");
+        }
+    }
+
+    #[test]
+    fn kebab_prefixes_words_starting_with_digits() {
+        assert_eq!(kebab("foo_2bar"), "foo-x2bar");
+        assert_eq!(kebab("2fast"), "x2fast");
+        assert_eq!(kebab("getValue2"), "getvalue2");
+        assert_eq!(kebab("__"), "unnamed");
+    }
+
+    #[test]
+    fn renders_parseable_wit_for_clashing_and_digit_led_names() {
+        // get-a-b is the hint of both the parameter's record and the nested record's field
+        let get = ExportFunction {
+            name: "get".to_string(),
+            parameters: vec![
+                FunctionParameter {
+                    name: "a-b".to_string(),
+                    typ: record(vec![("x", u32())]),
+                },
+                FunctionParameter {
+                    name: "a".to_string(),
+                    typ: record(vec![("b", record(vec![("y", u32())]))]),
+                },
+            ],
+            results: vec![FunctionResult {
+                name: None,
+                typ: Type::Tuple(TypeTuple {
+                    items: vec![record(vec![("z", u32())]), u32()],
+                }),
+            }],
+        };
+
+        let wit = render_wit(
+            &template(
+                "my_2nd_template",
+                vec![
+                    Export::Instance(ExportInstance {
+                        name: "golem:a/api".to_string(),
+                        functions: vec![get.clone()],
+                    }),
+                    Export::Instance(ExportInstance {
+                        name: "golem:b/api".to_string(),
+                        functions: vec![get],
+                    }),
+                ],
+            ),
+            None,
+        );
+
+        assert!(wit.contains("interface api-v2 {"), "{wit}");
+        assert!(wit.contains("record get-a-b-v2 {"), "{wit}");
+        assert!(wit.contains("world my-x2nd-template {"), "{wit}");
+        assert_parses(&wit);
+    }
+
+    #[test]
+    fn interface_and_world_functions_are_kebab_cased() {
+        let function = |name: &str| ExportFunction {
+            name: name.to_string(),
+            parameters: vec![FunctionParameter {
+                name: "someValue".to_string(),
+                typ: u32(),
+            }],
+            results: Vec::new(),
+        };
+
+        let wit = render_wit(
+            &template(
+                "template",
+                vec![
+                    Export::Instance(ExportInstance {
+                        name: "golem:it/api".to_string(),
+                        functions: vec![function("get_Value"), function("[method]counter.add_One")],
+                    }),
+                    Export::Function(function("set_Value")),
+                ],
+            ),
+            None,
+        );
+
+        assert!(
+            wit.contains("    get-value: func(somevalue: u32);"),
+            "{wit}"
+        );
+        assert!(wit.contains("add-one: func();"), "{wit}");
+        assert!(
+            wit.contains("export set-value: func(somevalue: u32);"),
+            "{wit}"
+        );
+        assert_parses(&wit);
+    }
+}
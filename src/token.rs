@@ -12,12 +12,22 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+mod audit;
+
+use std::fs::create_dir_all;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
 use async_trait::async_trait;
-use chrono::{DateTime, Utc};
-use clap::Subcommand;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use clap::{Args, Subcommand};
+use golem_cloud_client::model::{Token, UnsafeToken};
+use tempfile::NamedTempFile;
 
+use crate::auth::save_token;
 use crate::clients::token::TokenClient;
 use crate::clients::CloudAuthentication;
+use crate::config::{parse_profile_name, Config};
 use crate::model::{AccountId, GolemError, GolemResult, TokenId};
 
 fn parse_instant(
@@ -29,6 +39,84 @@ fn parse_instant(
     }
 }
 
+/// Parses a relative duration such as `90m`, `12h`, `30d` or `4w`, from now.
+///
+/// Durations reaching past the latest representable time are rejected.
+fn parse_duration(s: &str) -> Result<Duration, String> {
+    let (amount, unit) = s.split_at(s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len()));
+    let amount: i64 = amount.parse().map_err(|_| {
+        format!("Invalid duration: {s}. Expected a number followed by s, m, h, d or w")
+    })?;
+
+    let unit_seconds: i64 = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        "w" => 7 * 24 * 60 * 60,
+        _ => {
+            return Err(format!(
+                "Unknown duration unit in {s}. Expected one of \"s\", \"m\", \"h\", \"d\", \"w\""
+            ))
+        }
+    };
+
+    amount
+        .checked_mul(unit_seconds)
+        .filter(|seconds| *seconds <= Duration::max_value().num_seconds())
+        .map(Duration::seconds)
+        .filter(|duration| Utc::now().checked_add_signed(*duration).is_some())
+        .ok_or_else(|| format!("Duration {s} is too long"))
+}
+
+/// Parses a number of days, limited like the durations of [parse_duration].
+fn parse_days(s: &str) -> Result<i64, String> {
+    let days: u32 = s
+        .parse()
+        .map_err(|_| format!("Invalid number of days: {s}"))?;
+    parse_duration(&format!("{days}d"))?;
+
+    Ok(days.into())
+}
+
+/// Tokens expiring at or after this are treated as never expiring.
+fn never_expires() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2100, 1, 1, 0, 0, 0).unwrap()
+}
+
+fn warn_never_expires(tokens: &[&Token]) {
+    for token in tokens.iter().filter(|t| t.expires_at >= never_expires()) {
+        eprintln!(
+            "Warning: token {} never expires, consider using --expires-in",
+            token.id
+        );
+    }
+}
+
+#[derive(Args, Debug)]
+#[group(multiple = false)]
+pub struct TokenExpiry {
+    /// Expiration date of the generated token, never expires when no expiry is given
+    #[arg(long, value_parser = parse_instant)]
+    expires_at: Option<DateTime<Utc>>,
+
+    /// Time until the generated token expires, such as `12h`, `30d` or `4w`
+    #[arg(long, value_parser = parse_duration)]
+    expires_in: Option<Duration>,
+}
+
+impl TokenExpiry {
+    fn expires_at(&self) -> Result<DateTime<Utc>, GolemError> {
+        match (self.expires_at, self.expires_in) {
+            (Some(expires_at), _) => Ok(expires_at),
+            (None, Some(expires_in)) => Utc::now()
+                .checked_add_signed(expires_in)
+                .ok_or_else(|| GolemError::new("The expiry is too far in the future".to_string())),
+            (None, None) => Ok(never_expires()),
+        }
+    }
+}
+
 #[derive(Subcommand, Debug)]
 #[command()]
 pub enum TokenSubcommand {
@@ -36,12 +124,61 @@ pub enum TokenSubcommand {
     #[command()]
     List {},
 
+    /// Get the details of a token
+    #[command()]
+    Get {
+        #[arg(value_name = "TOKEN")]
+        token_id: TokenId,
+    },
+
     /// Add a new token
     #[command()]
     Add {
-        /// Expiration date of the generated token
-        #[arg(long, value_parser = parse_instant, default_value = "2100-01-01T00:00:00Z")]
-        expires_at: DateTime<Utc>,
+        #[command(flatten)]
+        expiry: TokenExpiry,
+    },
+
+    /// Replace a token with a new one, storing the new secret before deleting the old token
+    #[command()]
+    Rotate {
+        /// The token to replace
+        #[arg(value_name = "TOKEN")]
+        token_id: TokenId,
+
+        /// File to write the new token to, as read by `--auth-token-file`
+        #[arg(
+            long,
+            required_unless_present = "profile_name",
+            conflicts_with = "profile_name"
+        )]
+        output_file: Option<PathBuf>,
+
+        /// Profile to store the new token for, in the configured credential store
+        #[arg(long, value_parser = parse_profile_name)]
+        profile_name: Option<String>,
+
+        #[command(flatten)]
+        expiry: TokenExpiry,
+    },
+
+    /// Lists expired tokens, tokens expiring soon and tokens expiring too far out
+    #[command()]
+    Audit {
+        /// Flag tokens expiring within this time, such as `12h` or `7d`
+        #[arg(long, value_parser = parse_duration, default_value = "7d")]
+        expiring_within: Duration,
+
+        /// Flag tokens expiring more than this many days from now
+        #[arg(long, value_name = "DAYS", value_parser = parse_days, default_value = "365")]
+        max_days: i64,
+
+        /// Delete the flagged tokens, except the one the CLI is using
+        #[arg(long)]
+        delete: bool,
+
+        /// Delete without asking for confirmation
+        #[arg(short, long, requires = "delete")]
+        yes: bool,
     },
 
     /// Delete an existing token
@@ -52,6 +189,24 @@ pub enum TokenSubcommand {
     },
 }
 
+/// Writes the token through a temporary file, so the file always holds a complete token.
+fn write_token_file(path: &Path, token: &UnsafeToken) -> Result<(), GolemError> {
+    let write_err = |e: std::io::Error| GolemError::new(format!("Failed to write {path:?}: {e}"));
+
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    create_dir_all(dir).map_err(write_err)?;
+
+    // Created next to the target with mode 0600, and removed on drop if never persisted
+    let mut tmp = NamedTempFile::new_in(dir).map_err(write_err)?;
+    writeln!(tmp, "{}", token.secret.value).map_err(write_err)?;
+    tmp.persist(path).map_err(|e| write_err(e.error))?;
+
+    Ok(())
+}
+
 #[async_trait]
 pub trait TokenHandler {
     async fn handle(
@@ -64,6 +219,84 @@ pub trait TokenHandler {
 
 pub struct TokenHandlerLive<C: TokenClient + Send + Sync> {
     pub client: C,
+    pub config_dir: PathBuf,
+}
+
+impl<C: TokenClient + Send + Sync> TokenHandlerLive<C> {
+    fn store(
+        &self,
+        token: &UnsafeToken,
+        output_file: Option<&Path>,
+        profile_name: Option<&str>,
+    ) -> Result<String, GolemError> {
+        match (output_file, profile_name) {
+            (Some(path), _) => {
+                write_token_file(path, token)?;
+                Ok(path.display().to_string())
+            }
+            (None, Some(name)) => {
+                let mut config = Config::read(&self.config_dir)?;
+                let store = config.credential_store.unwrap_or_default();
+                let dir = Config::profile_dir(&self.config_dir, name)?;
+                store.check_available(&dir)?;
+
+                // A token in the profile would be used before the stored one
+                let profile = config
+                    .profiles
+                    .get_mut(name)
+                    .ok_or_else(|| GolemError::new(format!("Unknown profile {name}")))?;
+                let had_token = profile.auth_token.take().is_some();
+
+                save_token(store, token, &dir)?;
+                if had_token {
+                    config.store(&self.config_dir)?;
+                }
+
+                Ok(format!("{} (profile {name})", store.location(&dir)))
+            }
+            (None, None) => Err(GolemError::new(
+                "Either an output file or a profile is required".to_string(),
+            )),
+        }
+    }
+
+    /// The old token is only deleted once the new one is stored, and the new one is deleted
+    /// again when storing fails, so exactly one of them is valid afterwards.
+    async fn rotate(
+        &self,
+        account_id: &AccountId,
+        token_id: TokenId,
+        output_file: Option<&Path>,
+        profile_name: Option<&str>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<GolemResult, GolemError> {
+        let old = self.client.get(account_id, token_id.clone()).await?;
+        let new = self.client.post(account_id, expires_at).await?;
+
+        let location = match self.store(&new, output_file, profile_name) {
+            Ok(location) => location,
+            Err(err) => {
+                self.client.delete(account_id, TokenId(new.data.id)).await?;
+                return Err(err);
+            }
+        };
+
+        self.client
+            .delete(account_id, token_id)
+            .await
+            .map_err(|e| {
+                GolemError::new(format!(
+                    "Stored the new token {} in {location}, but failed to delete token {}: {}",
+                    new.data.id, old.id, e.message
+                ))
+            })?;
+
+        warn_never_expires(&[&new.data]);
+        Ok(GolemResult::Str(format!(
+            "Replaced token {} with token {}, stored in {location}",
+            old.id, new.data.id
+        )))
+    }
 }
 
 #[async_trait]
@@ -78,12 +311,43 @@ impl<C: TokenClient + Send + Sync> TokenHandler for TokenHandlerLive<C> {
         match subcommand {
             TokenSubcommand::List {} => {
                 let token = self.client.get_all(&account_id).await?;
+                warn_never_expires(&token.iter().collect::<Vec<_>>());
+                Ok(GolemResult::Ok(Box::new(token)))
+            }
+            TokenSubcommand::Get { token_id } => {
+                let token = self.client.get(&account_id, token_id).await?;
+                warn_never_expires(&[&token]);
                 Ok(GolemResult::Ok(Box::new(token)))
             }
-            TokenSubcommand::Add { expires_at } => {
-                let token = self.client.post(&account_id, expires_at).await?;
+            TokenSubcommand::Add { expiry } => {
+                let token = self.client.post(&account_id, expiry.expires_at()?).await?;
+                warn_never_expires(&[&token.data]);
                 Ok(GolemResult::Ok(Box::new(token)))
             }
+            TokenSubcommand::Rotate {
+                token_id,
+                output_file,
+                profile_name,
+                expiry,
+            } => {
+                self.rotate(
+                    &account_id,
+                    token_id,
+                    output_file.as_deref(),
+                    profile_name.as_deref(),
+                    expiry.expires_at()?,
+                )
+                .await
+            }
+            TokenSubcommand::Audit {
+                expiring_within,
+                max_days,
+                delete,
+                yes,
+            } => {
+                self.audit(auth, &account_id, expiring_within, max_days, delete, yes)
+                    .await
+            }
             TokenSubcommand::Delete { token_id } => {
                 self.client.delete(&account_id, token_id).await?;
                 Ok(GolemResult::Str("Deleted".to_string()))
@@ -91,3 +355,101 @@ impl<C: TokenClient + Send + Sync> TokenHandler for TokenHandlerLive<C> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+    use golem_cloud_client::model::{Token, TokenSecret, UnsafeToken};
+    use uuid::Uuid;
+
+    use crate::token::{parse_days, parse_duration, write_token_file};
+
+    #[test]
+    fn durations_have_a_unit() {
+        assert_eq!(parse_duration("90s"), Ok(Duration::seconds(90)));
+        assert_eq!(parse_duration("90m"), Ok(Duration::minutes(90)));
+        assert_eq!(parse_duration("12h"), Ok(Duration::hours(12)));
+        assert_eq!(parse_duration("30d"), Ok(Duration::days(30)));
+        assert_eq!(parse_duration("4w"), Ok(Duration::weeks(4)));
+
+        assert!(parse_duration("30").is_err());
+        assert!(parse_duration("30y").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("-3d").is_err());
+    }
+
+    #[test]
+    fn durations_past_the_latest_time_are_rejected() {
+        assert_eq!(
+            parse_duration(&format!("{}w", i64::MAX)),
+            Err(format!("Duration {}w is too long", i64::MAX))
+        );
+        assert!(parse_duration("100000000000d").is_err());
+    }
+
+    #[test]
+    fn days_are_positive_and_bounded() {
+        assert_eq!(parse_days("0"), Ok(0));
+        assert_eq!(parse_days("90"), Ok(90));
+
+        assert!(parse_days("-1").is_err());
+        assert!(parse_days("1d").is_err());
+        assert!(parse_days(&u32::MAX.to_string()).is_err());
+    }
+
+    fn token() -> UnsafeToken {
+        UnsafeToken {
+            data: Token {
+                id: Uuid::from_u128(rand::random()),
+                account_id: "account".to_string(),
+                created_at: Utc::now(),
+                expires_at: Utc::now() + Duration::days(1),
+            },
+            secret: TokenSecret {
+                value: Uuid::from_u128(rand::random()),
+            },
+        }
+    }
+
+    #[test]
+    fn token_files_leave_their_neighbours_alone() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ci");
+        let neighbour = dir.path().join("ci.tmp");
+        std::fs::write(&neighbour, "unrelated").unwrap();
+
+        let token = token();
+        write_token_file(&path, &token).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", token.secret.value)
+        );
+        assert_eq!(std::fs::read_to_string(&neighbour).unwrap(), "unrelated");
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 2);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn token_files_can_be_named_tmp() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("token.tmp");
+
+        let token = token();
+        write_token_file(&path, &token).unwrap();
+        write_token_file(&path, &token).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            format!("{}\n", token.secret.value)
+        );
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+}
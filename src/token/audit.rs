@@ -0,0 +1,244 @@
+// Copyright 2024 Golem Cloud
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use chrono::{DateTime, Duration, Utc};
+use golem_cloud_client::model::Token;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::clients::token::TokenClient;
+use crate::clients::CloudAuthentication;
+use crate::confirm;
+use crate::model::{AccountId, GolemError, GolemResult, TokenId};
+use crate::table::TableColumns;
+use crate::token::TokenHandlerLive;
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum TokenFinding {
+    Expired,
+    ExpiringSoon,
+    LongLived,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditedToken {
+    pub token_id: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    pub finding: TokenFinding,
+    /// The token the CLI is authenticated with
+    pub current: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenAuditView {
+    pub flagged: Vec<AuditedToken>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub deleted: Vec<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed: Vec<FailedDeletion>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FailedDeletion {
+    pub token_id: Uuid,
+    pub error: String,
+}
+
+impl TableColumns for TokenAuditView {}
+
+fn finding(
+    token: &Token,
+    now: DateTime<Utc>,
+    expiring_within: Duration,
+    max_days: i64,
+) -> Option<TokenFinding> {
+    if token.expires_at <= now {
+        Some(TokenFinding::Expired)
+    } else if now
+        .checked_add_signed(expiring_within)
+        .is_none_or(|limit| token.expires_at <= limit)
+    {
+        Some(TokenFinding::ExpiringSoon)
+    } else if now
+        .checked_add_signed(Duration::days(max_days))
+        .is_some_and(|limit| token.expires_at > limit)
+    {
+        Some(TokenFinding::LongLived)
+    } else {
+        None
+    }
+}
+
+impl<C: TokenClient + Send + Sync> TokenHandlerLive<C> {
+    pub(super) async fn audit(
+        &self,
+        auth: &CloudAuthentication,
+        account_id: &AccountId,
+        expiring_within: Duration,
+        max_days: i64,
+        delete: bool,
+        yes: bool,
+    ) -> Result<GolemResult, GolemError> {
+        let CloudAuthentication(current) = auth;
+        let now = Utc::now();
+
+        let flagged: Vec<AuditedToken> = self
+            .client
+            .get_all(account_id)
+            .await?
+            .into_iter()
+            .filter_map(|token| {
+                finding(&token, now, expiring_within, max_days).map(|finding| AuditedToken {
+                    token_id: token.id,
+                    created_at: token.created_at,
+                    expires_at: token.expires_at,
+                    finding,
+                    current: token.id == current.data.id,
+                })
+            })
+            .collect();
+
+        let mut deleted = Vec::new();
+        let mut failed = Vec::new();
+
+        if delete {
+            // Deleting the token in use would log the CLI out in the middle of the audit
+            if flagged.iter().any(|t| t.current) {
+                eprintln!(
+                    "Not deleting token {}, the CLI is using it",
+                    current.data.id
+                );
+            }
+            let to_delete: Vec<Uuid> = flagged
+                .iter()
+                .filter(|t| !t.current)
+                .map(|t| t.token_id)
+                .collect();
+
+            if !to_delete.is_empty()
+                && (yes || confirm(&format!("Delete {} flagged tokens?", to_delete.len()))?)
+            {
+                // One failure must not hide which tokens are already gone
+                for token_id in to_delete {
+                    match self.client.delete(account_id, TokenId(token_id)).await {
+                        Ok(_) => deleted.push(token_id),
+                        Err(err) => failed.push(FailedDeletion {
+                            token_id,
+                            error: err.message,
+                        }),
+                    }
+                }
+            }
+
+            if !failed.is_empty() {
+                eprintln!(
+                    "Failed to delete {} of {} tokens",
+                    failed.len(),
+                    failed.len() + deleted.len()
+                );
+            }
+        }
+
+        Ok(GolemResult::Ok(Box::new(TokenAuditView {
+            flagged,
+            deleted,
+            failed,
+        })))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, Utc};
+    use golem_cloud_client::model::Token;
+    use uuid::Uuid;
+
+    use crate::token::audit::{finding, TokenFinding};
+
+    fn expiring_at(expires_at: DateTime<Utc>) -> Token {
+        Token {
+            id: Uuid::from_u128(rand::random()),
+            account_id: "account".to_string(),
+            created_at: expires_at - Duration::days(365),
+            expires_at,
+        }
+    }
+
+    fn finding_in(now: DateTime<Utc>, expires_in: Duration) -> Option<TokenFinding> {
+        finding(&expiring_at(now + expires_in), now, Duration::days(7), 90)
+    }
+
+    #[test]
+    fn expired_includes_the_current_instant() {
+        let now = Utc::now();
+
+        assert_eq!(
+            finding_in(now, Duration::zero()),
+            Some(TokenFinding::Expired)
+        );
+        assert_eq!(
+            finding_in(now, -Duration::days(1)),
+            Some(TokenFinding::Expired)
+        );
+        assert_eq!(
+            finding_in(now, Duration::seconds(1)),
+            Some(TokenFinding::ExpiringSoon)
+        );
+    }
+
+    #[test]
+    fn expiring_soon_includes_the_limit() {
+        let now = Utc::now();
+
+        assert_eq!(
+            finding_in(now, Duration::days(7)),
+            Some(TokenFinding::ExpiringSoon)
+        );
+        assert_eq!(
+            finding_in(now, Duration::days(7) + Duration::seconds(1)),
+            None
+        );
+    }
+
+    #[test]
+    fn long_lived_excludes_the_limit() {
+        let now = Utc::now();
+
+        assert_eq!(finding_in(now, Duration::days(90)), None);
+        assert_eq!(
+            finding_in(now, Duration::days(90) + Duration::seconds(1)),
+            Some(TokenFinding::LongLived)
+        );
+    }
+
+    #[test]
+    fn unbounded_windows_do_not_overflow() {
+        let now = Utc::now();
+        let token = expiring_at(now + Duration::days(30));
+
+        assert_eq!(
+            finding(&token, now, Duration::max_value(), 90),
+            Some(TokenFinding::ExpiringSoon)
+        );
+        assert_eq!(
+            finding(&token, now, Duration::days(7), i64::MAX / 86_400_000),
+            None
+        );
+    }
+}
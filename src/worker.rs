@@ -275,8 +275,8 @@ impl<'r, C: WorkerClient + Send + Sync, R: TemplateHandler + Send + Sync> Worker
                 let result = self.client.connect(worker_name, template_id).await;
 
                 match result {
-                    Ok(_) => Err(GolemError("Unexpected connection closure".to_string())),
-                    Err(err) => Err(GolemError(err.to_string())),
+                    Ok(_) => Err(GolemError::new("Unexpected connection closure".to_string())),
+                    Err(err) => Err(GolemError::new(err.to_string())),
                 }
             }
             WorkerSubcommand::Interrupt {